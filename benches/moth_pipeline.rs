@@ -0,0 +1,137 @@
+use std::hint::black_box;
+
+use bevy::ecs::system::ScheduleSystem;
+use bevy::prelude::*;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use phototaxis::components::{Lantern, Moth};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::resources::SpatialIndex;
+use phototaxis::simulation::headless_app;
+use phototaxis::systems::{
+    enforce_boundary_system, moth_attraction_system, moth_collision_system, moth_movement_system,
+    moth_perch_system, moth_wander_system, wing_flap_system,
+};
+
+const SIZES: [(i32, usize); 3] = [(1_000, 16), (10_000, 64), (100_000, 256)];
+
+/// A headless app that has run its startup and one tick, with every lantern
+/// lit so attraction does its full amount of work.
+fn populated_app(moths: i32, lanterns: usize) -> App {
+    let mut app = headless_app(0);
+    app.insert_resource(MothConfig {
+        moth_count: moths,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Random {
+            count: lanterns,
+            min_spacing: 0.0,
+        },
+        ..default()
+    });
+    app.update();
+
+    let world = app.world_mut();
+    for mut lantern in world.query::<&mut Lantern>().iter_mut(world) {
+        lantern.is_on = true;
+        lantern.radiance = 10.0;
+    }
+    app
+}
+
+/// `systems` is called once per size, since chained system sets can't be
+/// cloned.
+fn bench_system<S: IntoScheduleConfigs<ScheduleSystem, M>, M>(
+    c: &mut Criterion,
+    name: &str,
+    systems: impl Fn() -> S,
+) {
+    let mut group = c.benchmark_group(name);
+    for (moths, lanterns) in SIZES {
+        let mut app = populated_app(moths, lanterns);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(systems());
+        group.bench_function(
+            BenchmarkId::from_parameter(format!("{moths}x{lanterns}")),
+            |b| b.iter(|| schedule.run(app.world_mut())),
+        );
+    }
+    group.finish();
+}
+
+fn attraction(c: &mut Criterion) {
+    bench_system(c, "moth_attraction_system", || moth_attraction_system);
+}
+
+fn collision(c: &mut Criterion) {
+    bench_system(c, "moth_collision_system", || moth_collision_system);
+}
+
+fn wing_flap(c: &mut Criterion) {
+    bench_system(c, "wing_flap_system", || wing_flap_system);
+}
+
+fn full_chain(c: &mut Criterion) {
+    bench_system(c, "moth_update_chain", || {
+        (
+            moth_perch_system,
+            moth_wander_system,
+            moth_attraction_system,
+            moth_movement_system,
+            enforce_boundary_system,
+            moth_collision_system,
+        )
+            .chain()
+    });
+}
+
+fn spatial_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_index");
+    for (moths, lanterns) in SIZES {
+        let mut app = populated_app(moths, lanterns);
+        let world = app.world_mut();
+        let lantern_positions: Vec<(Entity, Vec2)> = world
+            .query_filtered::<(Entity, &Transform), With<Lantern>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, transform.translation.xz()))
+            .collect();
+        let moth_positions: Vec<Vec2> = world
+            .query_filtered::<&Transform, With<Moth>>()
+            .iter(world)
+            .map(|transform| transform.translation.xz())
+            .collect();
+        let id = format!("{moths}x{lanterns}");
+
+        group.bench_function(BenchmarkId::new("rebuild", &id), |b| {
+            b.iter(|| {
+                let mut index = SpatialIndex::default();
+                for &(entity, position) in &lantern_positions {
+                    index.insert(entity, position);
+                }
+                black_box(index)
+            })
+        });
+
+        let index = world.resource::<SpatialIndex>();
+        group.bench_function(BenchmarkId::new("query", &id), |b| {
+            b.iter(|| {
+                moth_positions
+                    .iter()
+                    .map(|&position| index.get_nearby(position).len())
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    attraction,
+    collision,
+    wing_flap,
+    spatial_index,
+    full_chain
+);
+criterion_main!(benches);
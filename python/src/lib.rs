@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use phototaxis::api::{ApiCommand, ApiPlugin, ApiQueue, api_command_system, set_config_field};
+use phototaxis::components::{Lantern, LanternId, LightTrap, Moth, MothState, Velocity};
+use phototaxis::config::{LanternConfig, MothConfig, TrapConfig};
+use phototaxis::share::parse_layout;
+use phototaxis::simulation::headless_app;
+
+/// A headless simulation, stepped from Python.
+///
+/// Moth and lantern arrays list entities in the same order on every call
+/// until moths or lanterns are added or removed.
+#[pyclass(unsendable)]
+pub struct Simulation {
+    app: App,
+}
+
+fn value_error(err: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Flattens rows of three into an `(n, 3)` array.
+fn rows<'py>(py: Python<'py>, vectors: Vec<Vec3>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let count = vectors.len();
+    let flat: Vec<f32> = vectors.into_iter().flat_map(|row| row.to_array()).collect();
+    PyArray1::from_vec(py, flat).reshape([count, 3])
+}
+
+impl Simulation {
+    fn moths(&mut self) -> Vec<(Vec3, Vec3, MothState)> {
+        let world = self.app.world_mut();
+        world
+            .query_filtered::<(&Transform, &Velocity, &MothState), With<Moth>>()
+            .iter(world)
+            .map(|(transform, velocity, state)| (transform.translation, velocity.0, *state))
+            .collect()
+    }
+
+    fn lanterns(&mut self) -> Vec<(LanternId, Vec3, bool)> {
+        let world = self.app.world_mut();
+        world
+            .query::<(&LanternId, &Transform, &Lantern)>()
+            .iter(world)
+            .map(|(id, transform, lantern)| (*id, transform.translation, lantern.is_on))
+            .collect()
+    }
+
+    fn traps(&mut self) -> Vec<(LanternId, u32)> {
+        let world = self.app.world_mut();
+        world
+            .query::<(&LanternId, &LightTrap)>()
+            .iter(world)
+            .map(|(id, trap)| (*id, trap.catches))
+            .collect()
+    }
+
+    /// Applies an API command straight away rather than on the next step.
+    fn apply(&mut self, command: ApiCommand) -> PyResult<()> {
+        let world = self.app.world_mut();
+        world.resource::<ApiQueue>().push(command);
+        world
+            .run_system_cached(api_command_system)
+            .map_err(value_error)
+    }
+}
+
+#[pymethods]
+impl Simulation {
+    /// Sets the room up. `config` may hold a `seed` and any numeric
+    /// `moth.*` or `lantern.*` config field, like `moth.moth_count` or
+    /// `lantern.on_chance`, plus `lantern.layout` and `trap.layout` written as
+    /// in share links, like `"ring:6:5"`.
+    #[new]
+    #[pyo3(signature = (config=None))]
+    fn new(config: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut seed = 0;
+        let mut moth_config = MothConfig::default();
+        let mut lantern_config = LanternConfig::default();
+        let mut trap_config = TrapConfig::default();
+        for (key, value) in config.into_iter().flat_map(|config| config.iter()) {
+            let key: String = key.extract()?;
+            match key.as_str() {
+                "seed" => seed = value.extract()?,
+                "lantern.layout" | "trap.layout" => {
+                    let text: String = value.extract()?;
+                    let layout = parse_layout(&text)
+                        .ok_or_else(|| value_error(format!("invalid layout {text}")))?;
+                    if key == "trap.layout" {
+                        trap_config.layout = layout;
+                    } else {
+                        lantern_config.layout = layout;
+                    }
+                }
+                field => set_config_field(
+                    field,
+                    value.extract()?,
+                    &mut moth_config,
+                    &mut lantern_config,
+                )
+                .map_err(value_error)?,
+            }
+        }
+
+        let mut app = headless_app(seed);
+        app.add_plugins(ApiPlugin)
+            .insert_resource(moth_config)
+            .insert_resource(lantern_config)
+            .insert_resource(trap_config);
+        // The first update sets the room up and only starts the clock
+        app.update();
+        Ok(Self { app })
+    }
+
+    /// Advances `ticks` updates of 1/60 of a simulated second each.
+    #[pyo3(signature = (ticks=1))]
+    fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// Simulated seconds since the room was set up.
+    #[getter]
+    fn time(&self) -> f32 {
+        self.app.world().resource::<Time<Virtual>>().elapsed_secs()
+    }
+
+    /// Sets a numeric config field, named as in the constructor.
+    fn set_config(&mut self, field: String, value: f64) -> PyResult<()> {
+        let world = self.app.world_mut();
+        world.resource_scope(|world, mut moth_config: Mut<MothConfig>| {
+            let mut lantern_config = world.resource_mut::<LanternConfig>();
+            set_config_field(&field, value, &mut moth_config, &mut lantern_config)
+                .map_err(value_error)
+        })
+    }
+
+    /// Moth positions as an `(n, 3)` array.
+    fn moth_positions<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let positions = self.moths().into_iter().map(|(position, ..)| position);
+        rows(py, positions.collect())
+    }
+
+    /// Moth velocities as an `(n, 3)` array.
+    fn moth_velocities<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let velocities = self.moths().into_iter().map(|(_, velocity, _)| velocity);
+        rows(py, velocities.collect())
+    }
+
+    /// Whether each moth is attracted to a light.
+    fn moth_attracted<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        let attracted = self
+            .moths()
+            .into_iter()
+            .map(|(.., state)| state == MothState::Attracted);
+        PyArray1::from_vec(py, attracted.collect())
+    }
+
+    /// Lantern ids, which `set_lantern` takes.
+    fn lantern_ids<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let ids = self.lanterns().into_iter().map(|(id, ..)| id.0);
+        PyArray1::from_vec(py, ids.collect())
+    }
+
+    /// Lantern positions as an `(n, 3)` array.
+    fn lantern_positions<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let positions = self.lanterns().into_iter().map(|(_, position, _)| position);
+        rows(py, positions.collect())
+    }
+
+    /// Whether each lantern is on.
+    fn lantern_on<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        let on = self.lanterns().into_iter().map(|(.., is_on)| is_on);
+        PyArray1::from_vec(py, on.collect())
+    }
+
+    /// Switches a lantern on or off, or toggles it when `on` is `None`. It
+    /// stays that way until its own timer or chance changes it again.
+    #[pyo3(signature = (id, on=None))]
+    fn set_lantern(&mut self, id: u32, on: Option<bool>) -> PyResult<()> {
+        if !self
+            .lanterns()
+            .iter()
+            .any(|(lantern_id, ..)| lantern_id.0 == id)
+        {
+            return Err(value_error(format!("no lantern with id {id}")));
+        }
+        self.apply(ApiCommand::SetLantern { id, on })
+    }
+
+    /// Lantern ids of the light traps.
+    fn trap_ids<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let ids = self.traps().into_iter().map(|(id, _)| id.0);
+        PyArray1::from_vec(py, ids.collect())
+    }
+
+    /// How many moths each light trap has caught.
+    fn trap_catches<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let catches = self.traps().into_iter().map(|(_, catches)| catches);
+        PyArray1::from_vec(py, catches.collect())
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "phototaxis")]
+fn phototaxis_python(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Simulation>()
+}
@@ -0,0 +1,51 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use phototaxis_python::Simulation;
+
+#[test]
+#[ignore = "needs NumPy in the Python it links against; run with `just test-python`"]
+fn simulations_step_and_report_arrays() -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let config = PyDict::new(py);
+        config.set_item("seed", 7)?;
+        config.set_item("moth.moth_count", 20)?;
+        config.set_item("lantern.layout", "ring:3:5")?;
+        config.set_item("lantern.on_chance", 0.0)?;
+        let sim = py.get_type::<Simulation>().call1((config,))?;
+
+        sim.call_method1("step", (30,))?;
+        let time: f32 = sim.getattr("time")?.extract()?;
+        assert!((time - 0.5).abs() < 1e-3, "{time}");
+
+        let shape = |method: &str| -> PyResult<Vec<usize>> {
+            sim.call_method0(method)?.getattr("shape")?.extract()
+        };
+        assert_eq!(shape("moth_positions")?, [20, 3]);
+        assert_eq!(shape("moth_velocities")?, [20, 3]);
+        assert_eq!(shape("moth_attracted")?, [20]);
+        assert_eq!(shape("lantern_ids")?, [3]);
+        assert_eq!(shape("lantern_positions")?, [3, 3]);
+        assert_eq!(shape("lantern_on")?, [3]);
+
+        let list = |method: &str| sim.call_method0(method)?.call_method0("tolist");
+        let ids: Vec<u32> = list("lantern_ids")?.extract()?;
+        sim.call_method1("set_lantern", (ids[0], true))?;
+        let on: Vec<bool> = list("lantern_on")?.extract()?;
+        assert!(on[0]);
+        sim.call_method1("set_lantern", (ids[0],))?;
+        let on: Vec<bool> = list("lantern_on")?.extract()?;
+        assert!(!on[0]);
+        assert!(sim.call_method1("set_lantern", (u32::MAX,)).is_err());
+
+        sim.call_method1("set_config", ("moth.attraction_factor", 2.0))?;
+        assert!(
+            sim.call_method1("set_config", ("moth.colour", 1.0))
+                .is_err()
+        );
+        sim.call_method0("step")?;
+        assert_eq!(shape("moth_positions")?, [20, 3]);
+        Ok(())
+    })
+}
@@ -0,0 +1,297 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::reflect::GetPath;
+use bevy_rand::prelude::{Global, GlobalEntropy, WyRand};
+use rand::Rng;
+
+use crate::components::{Lantern, LanternId, Moth, MothState};
+use crate::config::{LanternConfig, MothConfig};
+use crate::events::{LanternTurnedOff, LanternTurnedOn};
+use crate::resources::MothAssets;
+use crate::setup::moth_bundle;
+use crate::simulation::MothUpdate;
+use crate::systems::{SwitchableLantern, light_intensity, switch_off, switch_on};
+
+/// Something code outside the app asked for, such as the page embedding the
+/// web build.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiCommand {
+    SetPaused(bool),
+    /// Sets how fast simulated time runs relative to real time. Negative
+    /// speeds stop it, and speeds that aren't finite are ignored.
+    SetSpeed(f64),
+    /// Sets a numeric field of `MothConfig` or `LanternConfig`, named like
+    /// `moth.attraction_factor` or `lantern.on_chance`.
+    SetConfig {
+        field: String,
+        value: f64,
+    },
+    /// Switches the lantern with this id on or off, or toggles it when `on`
+    /// is `None`.
+    SetLantern {
+        id: u32,
+        on: Option<bool>,
+    },
+    SpawnMoths {
+        count: usize,
+        position: Vec3,
+    },
+}
+
+/// Commands waiting for the next frame. Cloning shares the queue, so code
+/// without access to the world can push to it.
+#[derive(Resource, Clone, Default)]
+pub struct ApiQueue(Arc<Mutex<Vec<ApiCommand>>>);
+
+impl ApiQueue {
+    pub fn push(&self, command: ApiCommand) {
+        self.0.lock().unwrap().push(command);
+    }
+
+    fn take(&self) -> Vec<ApiCommand> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// A lantern as reported in `ApiMetrics`. The id is its `LanternId`, which
+/// `SetLantern` takes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LanternStatus {
+    pub id: u32,
+    pub position: Vec3,
+    pub is_on: bool,
+    pub intensity: f32,
+}
+
+/// A summary of the simulation, sent every `ApiConfig::metrics_interval`.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct ApiMetrics {
+    /// Simulated seconds since the app started.
+    pub time: f32,
+    pub paused: bool,
+    pub speed: f64,
+    pub moths: usize,
+    /// Fraction of moths attracted to a light.
+    pub attracted: f32,
+    pub lanterns: Vec<LanternStatus>,
+}
+
+#[derive(Resource)]
+pub struct ApiConfig {
+    /// Seconds of wall-clock time between `ApiMetrics`.
+    pub metrics_interval: f32,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            metrics_interval: 0.5,
+        }
+    }
+}
+
+/// Lets code outside the app drive it through an `ApiQueue` and follow it
+/// through `ApiMetrics` events.
+pub struct ApiPlugin;
+
+impl Plugin for ApiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ApiQueue>()
+            .init_resource::<ApiConfig>()
+            .add_event::<ApiMetrics>()
+            .add_systems(
+                Update,
+                (
+                    api_command_system.before(MothUpdate),
+                    api_metrics_system.after(MothUpdate),
+                ),
+            );
+    }
+}
+
+/// Sets a numeric field of `MothConfig` or `LanternConfig`, named like
+/// `moth.attraction_factor` or `lantern.on_chance`.
+pub fn set_config_field(
+    field: &str,
+    value: f64,
+    moth_config: &mut MothConfig,
+    lantern_config: &mut LanternConfig,
+) -> Result {
+    update_config_field(field, |_| value, moth_config, lantern_config)
+}
+
+/// Replaces a numeric config field, named as for `set_config_field`, with
+/// `update` of its current value.
+pub fn update_config_field(
+    field: &str,
+    update: impl FnOnce(f64) -> f64,
+    moth_config: &mut MothConfig,
+    lantern_config: &mut LanternConfig,
+) -> Result {
+    let target = match field.split_once('.') {
+        Some(("moth", path)) => moth_config.reflect_path_mut(path),
+        Some(("lantern", path)) => lantern_config.reflect_path_mut(path),
+        _ => return Err(format!("unknown config field {field}").into()),
+    }
+    .map_err(|err| format!("unknown config field {field}: {err}"))?;
+
+    if let Some(target) = target.try_downcast_mut::<f32>() {
+        *target = update(*target as f64) as f32;
+    } else if let Some(target) = target.try_downcast_mut::<f64>() {
+        *target = update(*target);
+    } else if let Some(target) = target.try_downcast_mut::<i32>() {
+        *target = update(*target as f64).round() as i32;
+    } else {
+        return Err(format!("{field} isn't a number").into());
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn api_command_system(
+    mut commands: Commands,
+    queue: Res<ApiQueue>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut moth_config: ResMut<MothConfig>,
+    mut lantern_config: ResMut<LanternConfig>,
+    mut lantern_query: Query<(Entity, &LanternId, SwitchableLantern), Without<Global>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    moth_assets: Option<Res<MothAssets>>,
+    mut rng: GlobalEntropy<WyRand>,
+    mut turned_on: EventWriter<LanternTurnedOn>,
+    mut turned_off: EventWriter<LanternTurnedOff>,
+) {
+    for command in queue.take() {
+        match command {
+            ApiCommand::SetPaused(true) => virtual_time.pause(),
+            ApiCommand::SetPaused(false) => virtual_time.unpause(),
+            ApiCommand::SetSpeed(speed) if speed.is_finite() => {
+                virtual_time.set_relative_speed_f64(speed.max(0.0));
+            }
+            ApiCommand::SetSpeed(speed) => warn!("Ignoring speed {speed}, which isn't finite"),
+            ApiCommand::SetConfig { field, value } => {
+                if let Err(err) =
+                    set_config_field(&field, value, &mut moth_config, &mut lantern_config)
+                {
+                    warn!("Ignoring config change: {err}");
+                }
+            }
+            ApiCommand::SetLantern { id, on } => {
+                let Some((entity, _, (light, mut lantern, material_handle, mut lantern_rng))) =
+                    lantern_query
+                        .iter_mut()
+                        .find(|(_, lantern_id, _)| lantern_id.0 == id)
+                else {
+                    warn!("No lantern with id {id}");
+                    continue;
+                };
+                let on = on.unwrap_or(!lantern.is_on);
+                if on == lantern.is_on {
+                    continue;
+                }
+                let (Some(intensity), Some(material)) = (
+                    light_intensity(light),
+                    materials.get_mut(&material_handle.0),
+                ) else {
+                    continue;
+                };
+
+                if on {
+                    switch_on(
+                        intensity,
+                        &mut lantern,
+                        material,
+                        &mut lantern_rng,
+                        &lantern_config,
+                    );
+                    turned_on.write(LanternTurnedOn {
+                        entity,
+                        radiance: lantern.radiance,
+                        intensity: lantern.base_intensity,
+                    });
+                } else {
+                    turned_off.write(LanternTurnedOff {
+                        entity,
+                        radiance: lantern.radiance,
+                        intensity: lantern.base_intensity,
+                    });
+                    switch_off(intensity, &mut lantern, material);
+                }
+            }
+            ApiCommand::SpawnMoths { count, position } => {
+                let Some(assets) = &moth_assets else {
+                    continue;
+                };
+                for _ in 0..count {
+                    let velocity = Vec3::new(
+                        rng.random_range(-1.0..1.0),
+                        rng.random_range(-1.0..1.0),
+                        rng.random_range(-1.0..1.0),
+                    )
+                    .normalize_or_zero()
+                        * moth_config.moth_speed;
+                    let moth = moth_bundle(assets, position, velocity, &mut rng);
+                    commands.spawn(moth);
+                }
+            }
+        }
+    }
+}
+
+/// What a metrics report reads from a lantern.
+type ReportedLantern = (
+    &'static LanternId,
+    &'static Transform,
+    &'static Lantern,
+    AnyOf<(&'static PointLight, &'static SpotLight)>,
+);
+
+pub fn api_metrics_system(
+    mut since_report: Local<f32>,
+    real_time: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    config: Res<ApiConfig>,
+    moth_query: Query<&MothState, With<Moth>>,
+    lantern_query: Query<ReportedLantern>,
+    mut metrics: EventWriter<ApiMetrics>,
+) {
+    *since_report += real_time.delta_secs();
+    if *since_report < config.metrics_interval {
+        return;
+    }
+    *since_report = 0.0;
+
+    let moths = moth_query.iter().count();
+    let attracted = moth_query
+        .iter()
+        .filter(|state| **state == MothState::Attracted)
+        .count();
+    let lanterns = lantern_query
+        .iter()
+        .map(
+            |(id, transform, lantern, (point_light, spot_light))| LanternStatus {
+                id: id.0,
+                position: transform.translation,
+                is_on: lantern.is_on,
+                intensity: point_light
+                    .map(|light| light.intensity)
+                    .or(spot_light.map(|light| light.intensity))
+                    .unwrap_or_default(),
+            },
+        )
+        .collect();
+
+    metrics.write(ApiMetrics {
+        time: virtual_time.elapsed_secs(),
+        paused: virtual_time.is_paused(),
+        speed: virtual_time.relative_speed_f64(),
+        moths,
+        attracted: if moths > 0 {
+            attracted as f32 / moths as f32
+        } else {
+            0.0
+        },
+        lanterns,
+    });
+}
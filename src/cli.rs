@@ -0,0 +1,334 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bevy::log::{Level, LogPlugin};
+use bevy::prelude::*;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use phototaxis::components::{LanternId, LightTrap};
+use phototaxis::config::{LanternLayout, MothConfig, RecordingConfig, SnapshotConfig, TrapConfig};
+use phototaxis::resources::Recorder;
+use phototaxis::share::{ShareSettings, parse_layout};
+use phototaxis::simulation::headless_app_with_timestep;
+use phototaxis::snapshot::{restore_snapshot, save_snapshot};
+use phototaxis::sweep::{Sweep, write_csv};
+use phototaxis::systems::catch_curves_csv;
+use phototaxis::timeline::{Timeline, TimelinePlayer, TimelinePlugin};
+
+use crate::{WindowedOptions, windowed_app};
+
+/// A simulation of moth behaviour.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// How much to log.
+    #[arg(long, global = true, value_enum, default_value_t = LogLevel::Info)]
+    log_level: LogLevel,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Open the interactive simulation. This is the default.
+    Run(RunArgs),
+    /// Simulate without a window and save a snapshot of where it ended up.
+    Headless(HeadlessArgs),
+    /// Simulate without a window and save a recording for `replay`.
+    Record(HeadlessArgs),
+    /// Open a recording in the replay player.
+    Replay(ReplayArgs),
+    /// Run every combination in a sweep definition and write a CSV summary.
+    Sweep(SweepArgs),
+}
+
+#[derive(Args, Default)]
+struct SimulationArgs {
+    /// Seed for every random number generator. Random when left out.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// A saved snapshot to start from instead of a freshly set-up room.
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+    /// How many moths to spawn. Ignored when starting from a scenario.
+    #[arg(long)]
+    moth_count: Option<i32>,
+    /// Hang light traps, laid out as in share links, like `ring:4:6`. Their
+    /// catch curves are saved to `catches.csv` after headless runs.
+    #[arg(long, value_parser = parse_layout_arg)]
+    traps: Option<LanternLayout>,
+    /// A RON timeline of lantern, config, moth and camera changes to make
+    /// during the run.
+    #[arg(long)]
+    timeline: Option<PathBuf>,
+    /// Where snapshots, recordings and exports are written.
+    #[arg(long, default_value = ".")]
+    output_dir: PathBuf,
+}
+
+#[derive(Args, Default)]
+struct RunArgs {
+    #[command(flatten)]
+    simulation: SimulationArgs,
+}
+
+#[derive(Args)]
+struct HeadlessArgs {
+    #[command(flatten)]
+    simulation: SimulationArgs,
+    /// Seconds of simulated time to run for.
+    #[arg(long, default_value_t = 60.0)]
+    duration: f64,
+    /// Simulation updates per simulated second.
+    #[arg(long, default_value_t = 60.0)]
+    tick_rate: f64,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// The recording to play.
+    recording: PathBuf,
+}
+
+#[derive(Args)]
+struct SweepArgs {
+    /// A RON file listing the parameter values to combine.
+    definition: PathBuf,
+    /// Runs to do at once. Zero uses every core.
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+    /// Where `sweep.csv` is written.
+    #[arg(long, default_value = ".")]
+    output_dir: PathBuf,
+}
+
+#[derive(ValueEnum, Copy, Clone)]
+enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let level = cli.log_level.into();
+    let result = match cli.command.unwrap_or(Command::Run(RunArgs::default())) {
+        Command::Run(args) => run(args, level),
+        Command::Headless(args) => headless(args, level),
+        Command::Record(args) => record(args, level),
+        Command::Replay(args) => replay(args, level),
+        Command::Sweep(args) => sweep(args),
+    };
+
+    match result {
+        Ok(exit) => exit,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_layout_arg(text: &str) -> Result<LanternLayout, String> {
+    parse_layout(text).ok_or_else(|| format!("invalid layout {text}"))
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()).into())
+}
+
+fn read_timeline(path: &Path) -> Result<Timeline> {
+    Timeline::from_ron(&read(path)?)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()).into())
+}
+
+fn write(dir: &Path, file_name: &Path, text: &str) -> Result {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name);
+    fs::write(&path, text).map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+    eprintln!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn exit_code(exit: AppExit) -> ExitCode {
+    match exit {
+        AppExit::Success => ExitCode::SUCCESS,
+        AppExit::Error(code) => ExitCode::from(code.get()),
+    }
+}
+
+fn run(args: RunArgs, level: Level) -> Result<ExitCode> {
+    let SimulationArgs {
+        seed,
+        scenario,
+        moth_count,
+        traps,
+        timeline,
+        output_dir,
+    } = args.simulation;
+    let options = WindowedOptions {
+        share: ShareSettings {
+            seed,
+            moth_count,
+            ..default()
+        },
+        scenario: scenario.as_deref().map(read).transpose()?,
+        timeline: timeline.as_deref().map(read_timeline).transpose()?,
+        traps,
+        replay: None,
+        output_dir: Some(output_dir),
+        log_level: Some(level),
+    };
+    Ok(exit_code(windowed_app(options).run()))
+}
+
+fn replay(args: ReplayArgs, level: Level) -> Result<ExitCode> {
+    let options = WindowedOptions {
+        replay: Some(read(&args.recording)?),
+        log_level: Some(level),
+        ..default()
+    };
+    Ok(exit_code(windowed_app(options).run()))
+}
+
+/// Sets up a headless app from the command line, ready for its first
+/// simulated tick.
+fn headless_app(args: &HeadlessArgs, level: Level) -> Result<App> {
+    if !(args.tick_rate.is_finite() && args.tick_rate > 0.0) {
+        return Err("--tick-rate must be a positive number".into());
+    }
+    let timestep = Duration::try_from_secs_f64(args.tick_rate.recip())
+        .map_err(|_| "--tick-rate is too small")?;
+    let seed = args.simulation.seed.unwrap_or_else(rand::random);
+
+    let mut app = headless_app_with_timestep(seed, timestep);
+    app.add_plugins(LogPlugin { level, ..default() });
+    if let Some(moth_count) = args.simulation.moth_count {
+        app.insert_resource(MothConfig {
+            moth_count,
+            ..default()
+        });
+    }
+    if let Some(layout) = &args.simulation.traps {
+        app.insert_resource(TrapConfig {
+            layout: layout.clone(),
+            ..default()
+        });
+    }
+    if let Some(path) = &args.simulation.timeline {
+        app.add_plugins(TimelinePlugin)
+            .insert_resource(TimelinePlayer::new(read_timeline(path)?));
+    }
+    info!("Simulating with seed {seed}");
+
+    // The first update sets the room up and only starts the clock
+    app.update();
+    if let Some(path) = &args.simulation.scenario {
+        restore_snapshot(app.world_mut(), &read(path)?)?;
+    }
+    Ok(app)
+}
+
+fn ticks(args: &HeadlessArgs) -> usize {
+    (args.duration * args.tick_rate).round() as usize
+}
+
+/// Saves the catch curves of any light traps in the app.
+fn write_catches(app: &mut App, dir: &Path) -> Result {
+    let world = app.world_mut();
+    let mut trap_query = world.query::<(&LanternId, &Transform, &LightTrap)>();
+    if trap_query.iter(world).next().is_none() {
+        return Ok(());
+    }
+
+    let traps = trap_query
+        .iter(world)
+        .map(|(id, transform, trap)| (id.0, transform.translation, trap));
+    write(
+        dir,
+        &TrapConfig::default().export_path,
+        &catch_curves_csv(traps),
+    )
+}
+
+fn headless(args: HeadlessArgs, level: Level) -> Result<ExitCode> {
+    let mut app = headless_app(&args, level)?;
+    for _ in 0..ticks(&args) {
+        app.update();
+    }
+
+    let snapshot = save_snapshot(app.world_mut())?;
+    write(
+        &args.simulation.output_dir,
+        &SnapshotConfig::default().path,
+        &snapshot,
+    )?;
+    write_catches(&mut app, &args.simulation.output_dir)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn record(args: HeadlessArgs, level: Level) -> Result<ExitCode> {
+    let mut app = headless_app(&args, level)?;
+    app.world_mut().resource_mut::<Recorder>().start();
+    for _ in 0..ticks(&args) {
+        app.update();
+    }
+
+    let recording = app.world_mut().resource_mut::<Recorder>().stop();
+    write(
+        &args.simulation.output_dir,
+        &RecordingConfig::default().path,
+        &recording.to_ron()?,
+    )?;
+    write_catches(&mut app, &args.simulation.output_dir)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn sweep(args: SweepArgs) -> Result<ExitCode> {
+    let sweep = Sweep::from_ron(&read(&args.definition)?)?;
+    let threads = match args.threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+
+    let total = sweep.runs().len();
+    let done = AtomicUsize::new(0);
+    eprintln!("Running {total} runs on {threads} threads");
+    let summaries = sweep.run_all(threads, |summary| {
+        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+        let parameters = summary.parameters;
+        eprintln!(
+            "[{done}/{total}] seed {} attraction {} view {} on {}: {:.3} attracted",
+            parameters.seed,
+            parameters.attraction_factor,
+            parameters.view_radius,
+            parameters.on_chance,
+            summary.mean_attracted,
+        );
+    });
+
+    fs::create_dir_all(&args.output_dir)?;
+    let path = args.output_dir.join("sweep.csv");
+    write_csv(&summaries, BufWriter::new(File::create(&path)?))?;
+    eprintln!("Wrote {}", path.display());
+    Ok(ExitCode::SUCCESS)
+}
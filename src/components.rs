@@ -1,13 +1,126 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Moth;
 
-#[derive(Component, Copy, Clone)]
+#[derive(Component, Reflect, Copy, Clone, Default)]
+#[reflect(Component, Default)]
 pub struct Velocity(pub Vec3);
 
-#[derive(Component)]
+#[derive(Component, Reflect, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub enum MothState {
+    #[default]
+    Wandering,
+    /// Within view of at least one lit lantern.
+    Attracted,
+    /// Resting on a surface. See `Perch`.
+    Perched,
+}
+
+/// Where a perched moth is resting and for how long. Moths have one only
+/// while perched.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct Perch {
+    /// Points away from the surface, and is the way the moth takes off.
+    pub normal: Vec3,
+    /// The lantern the moth landed on, if it isn't on the room itself.
+    #[entities]
+    pub lantern: Option<Entity>,
+    /// Where the moth sits relative to its lantern.
+    pub offset: Vec3,
+    /// Runs out when the moth takes off.
+    pub timer: Timer,
+}
+
+/// What the moth noticed on its last attraction update.
+#[derive(Component, Copy, Clone, Default)]
+pub struct MothPerception {
+    /// The lantern pulling hardest on the moth, if any are in view.
+    pub strongest_light: Option<Entity>,
+}
+
+/// The steering applied to a moth on its last update.
+#[derive(Component, Reflect, Copy, Clone, Default)]
+#[reflect(Component, Default)]
+pub struct Steering {
+    /// Random jitter added by wandering.
+    pub wander: Vec3,
+    /// Acceleration towards lanterns.
+    pub attraction: Vec3,
+}
+
+#[derive(Copy, Clone)]
+pub struct TrailPoint {
+    pub position: Vec3,
+    pub speed: f32,
+    pub nearest_lantern: Option<Entity>,
+}
+
+/// Recent positions of a moth, oldest first.
+#[derive(Component, Default)]
+pub struct Trail {
+    pub points: VecDeque<TrailPoint>,
+}
+
+#[derive(Component, Reflect, Copy, Clone, Default)]
+#[reflect(Component, Default)]
+pub struct MothStats {
+    /// Seconds spent within view of a lit lantern.
+    pub time_near_lights: f32,
+}
+
+/// The kind of light fitting a lantern has. Cone angles are half-angles in
+/// radians, measured from the direction the fixture points.
+#[derive(Reflect, Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum Fixture {
+    /// A bare bulb that shines in every direction.
+    #[default]
+    Omni,
+    /// A spotlight pointing straight down.
+    Downlight { cone_angle: f32 },
+    /// A fully shielded fitting that emits nothing above the horizontal.
+    FullCutoff,
+    /// A spotlight pointing straight up.
+    Uplight { cone_angle: f32 },
+}
+
+impl Fixture {
+    /// The axis and half-angle of the emission cone, or `None` if the fixture
+    /// is omnidirectional.
+    pub fn cone(&self) -> Option<(Vec3, f32)> {
+        match *self {
+            Fixture::Omni => None,
+            Fixture::Downlight { cone_angle } => Some((Vec3::NEG_Y, cone_angle)),
+            Fixture::FullCutoff => Some((Vec3::NEG_Y, std::f32::consts::FRAC_PI_2)),
+            Fixture::Uplight { cone_angle } => Some((Vec3::Y, cone_angle)),
+        }
+    }
+
+    /// Whether the cone, if any, has a finite half-angle between zero and a
+    /// half turn.
+    pub fn is_valid(&self) -> bool {
+        self.cone()
+            .is_none_or(|(_, cone_angle)| cone_angle > 0.0 && cone_angle <= std::f32::consts::PI)
+    }
+
+    /// Whether light leaves the fixture in `direction`.
+    pub fn emits_towards(&self, direction: Vec3) -> bool {
+        match self.cone() {
+            Some((axis, cone_angle)) => direction.normalize_or_zero().dot(axis) >= cone_angle.cos(),
+            None => true,
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct Lantern {
+    pub fixture: Fixture,
     pub radiance: f32,
     pub is_on: bool,
     pub on_timer: Timer,
@@ -18,6 +131,7 @@ pub struct Lantern {
 impl Default for Lantern {
     fn default() -> Self {
         Self {
+            fixture: Fixture::default(),
             radiance: 1.0,
             is_on: false,
             on_timer: Timer::from_seconds(1.0, TimerMode::Once),
@@ -27,17 +141,204 @@ impl Default for Lantern {
     }
 }
 
-#[derive(Component)]
+/// Names a lantern to code outside the app. Unlike the entity, it isn't
+/// reused once the lantern is gone, and it's kept across snapshots.
+#[derive(Component, Reflect, Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub struct LanternId(pub u32);
+
+/// A lantern with a bucket hanging below it that moths fall into.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component, Default)]
+pub struct LightTrap {
+    /// Radius of the funnel mouth, centred under the lantern.
+    pub capture_radius: f32,
+    /// How far below the lantern the bucket reaches.
+    pub capture_depth: f32,
+    pub catches: u32,
+    /// Simulated seconds and the catches so far at that point, oldest first.
+    pub catch_curve: Vec<(f32, u32)>,
+}
+
+impl LightTrap {
+    /// Whether `point` is inside the bucket of a trap hanging at `position`.
+    pub fn contains(&self, position: Vec3, point: Vec3) -> bool {
+        let offset = point - position;
+        offset.y <= 0.0
+            && offset.y >= -self.capture_depth
+            && offset.xz().length_squared() <= self.capture_radius.powi(2)
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct LanternBob {
     pub initial_y: f32,
     pub phase_offset: f32,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Ceiling;
 
-#[derive(Component)]
-pub struct OrbitCamera {
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Rotating moves the camera around the focus point.
+    #[default]
+    Orbit,
+    /// Rotating turns the camera in place.
+    FreeFly,
+}
+
+/// A camera placement described as a point to look at and the yaw, pitch and
+/// distance to view it from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraRig {
+    pub focus: Vec3,
     pub radius: f32,
     pub angle: f32,
+    pub pitch: f32,
+}
+
+impl CameraRig {
+    pub fn eye(&self) -> Vec3 {
+        let horizontal = Vec2::from_angle(self.angle) * self.pitch.cos();
+        self.focus + Vec3::new(horizontal.x, self.pitch.sin(), horizontal.y) * self.radius
+    }
+
+    pub fn lerp(&self, other: &CameraRig, t: f32) -> CameraRig {
+        CameraRig {
+            focus: self.focus.lerp(other.focus, t),
+            radius: self.radius.lerp(other.radius, t),
+            angle: self.angle.lerp(other.angle, t),
+            pitch: self.pitch.lerp(other.pitch, t),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct OrbitCamera {
+    pub mode: CameraMode,
+    /// Where the camera currently is.
+    pub rig: CameraRig,
+    /// Where input has asked the camera to be. `rig` eases towards it.
+    pub goal: CameraRig,
+}
+
+#[derive(Component)]
+pub struct MothInspector;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeatmapProjection {
+    TopDown,
+    Side,
+}
+
+/// A quad in the room that displays one projection of the heatmap.
+#[derive(Component)]
+pub struct HeatmapSurface {
+    pub projection: HeatmapProjection,
+    pub image: Handle<Image>,
+}
+
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimeControlButton {
+    TogglePause,
+    Step,
+    Slower,
+    Faster,
+}
+
+#[derive(Component)]
+pub struct TimeControlLabel;
+
+#[derive(Component)]
+pub struct ShareButton;
+
+/// The playback controls, shown while a recording is loaded.
+#[derive(Component)]
+pub struct ReplayPanel;
+
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayButton {
+    Rewind,
+    PreviousEvent,
+    TogglePause,
+    NextEvent,
+    Close,
+}
+
+/// The bar that seeks through the recording when clicked or dragged.
+#[derive(Component)]
+pub struct ReplayScrubber;
+
+/// The part of the scrubber showing how much has played.
+#[derive(Component)]
+pub struct ReplayScrubberFill;
+
+#[derive(Component)]
+pub struct ReplayLabel;
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    /// A unit vector `angle` radians away from straight down, tipped towards +x.
+    fn from_down(angle: f32) -> Vec3 {
+        Vec3::new(angle.sin(), -angle.cos(), 0.0)
+    }
+
+    #[test]
+    fn omni_emits_everywhere() {
+        for direction in [Vec3::Y, Vec3::NEG_Y, Vec3::X, from_down(2.0)] {
+            assert!(Fixture::Omni.emits_towards(direction), "{direction}");
+        }
+    }
+
+    #[test]
+    fn downlight_emits_only_inside_its_cone() {
+        let fixture = Fixture::Downlight {
+            cone_angle: FRAC_PI_4,
+        };
+        assert!(fixture.emits_towards(Vec3::NEG_Y));
+        assert!(fixture.emits_towards(from_down(FRAC_PI_4 - 0.05)));
+        assert!(!fixture.emits_towards(from_down(FRAC_PI_4 + 0.05)));
+        assert!(!fixture.emits_towards(Vec3::X));
+        assert!(!fixture.emits_towards(Vec3::Y));
+    }
+
+    #[test]
+    fn full_cutoff_emits_nothing_above_the_horizontal() {
+        assert!(Fixture::FullCutoff.emits_towards(Vec3::NEG_Y));
+        assert!(Fixture::FullCutoff.emits_towards(from_down(1.5)));
+        assert!(!Fixture::FullCutoff.emits_towards(from_down(1.65)));
+        assert!(!Fixture::FullCutoff.emits_towards(Vec3::Y));
+    }
+
+    #[test]
+    fn uplight_emits_only_inside_its_cone() {
+        let fixture = Fixture::Uplight {
+            cone_angle: FRAC_PI_4,
+        };
+        assert!(fixture.emits_towards(Vec3::Y));
+        assert!(fixture.emits_towards(-from_down(FRAC_PI_4 - 0.05)));
+        assert!(!fixture.emits_towards(-from_down(FRAC_PI_4 + 0.05)));
+        assert!(!fixture.emits_towards(Vec3::X));
+        assert!(!fixture.emits_towards(Vec3::NEG_Y));
+    }
+
+    #[test]
+    fn cones_must_open() {
+        assert!(Fixture::Omni.is_valid());
+        assert!(Fixture::FullCutoff.is_valid());
+        for cone_angle in [0.0, -0.5, 4.0, f32::NAN, f32::INFINITY] {
+            assert!(
+                !Fixture::Downlight { cone_angle }.is_valid(),
+                "{cone_angle}"
+            );
+            assert!(!Fixture::Uplight { cone_angle }.is_valid(), "{cone_angle}");
+        }
+    }
 }
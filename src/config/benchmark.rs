@@ -0,0 +1,30 @@
+use bevy::prelude::Resource;
+
+/// Set through `PHOTOTAXIS_BENCHMARK=<moths>` to run a crowded scene and log
+/// frame times.
+#[derive(Resource)]
+pub struct BenchmarkConfig {
+    pub moth_count: i32,
+    /// Seconds of wall-clock time between frame time reports.
+    pub report_interval: f32,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            moth_count: 10_000,
+            report_interval: 5.0,
+        }
+    }
+}
+
+impl BenchmarkConfig {
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("PHOTOTAXIS_BENCHMARK").ok()?;
+        let mut config = Self::default();
+        if let Ok(moth_count) = value.parse() {
+            config.moth_count = moth_count;
+        }
+        Some(config)
+    }
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::{Resource, Vec3};
+
+#[derive(Resource)]
+pub struct CameraConfig {
+    pub focus: Vec3,
+    pub radius: f32,
+    pub angle: f32,
+    pub pitch: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    pub keyboard_speed: f32,
+    pub mouse_sensitivity: f32,
+    pub touch_speed: f32,
+    pub zoom_speed: f32,
+    pub fly_speed: f32,
+    /// How quickly the camera catches up with input. Higher is snappier.
+    pub damping: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::new(0.0, 5.0, 0.0),
+            radius: 18.0,
+            angle: std::f32::consts::FRAC_PI_2,
+            pitch: 0.14,
+            min_radius: 2.0,
+            max_radius: 40.0,
+            min_pitch: -1.4,
+            max_pitch: 1.4,
+            keyboard_speed: 1.5,
+            mouse_sensitivity: 0.005,
+            touch_speed: 0.1,
+            zoom_speed: 0.1,
+            fly_speed: 6.0,
+            damping: 12.0,
+        }
+    }
+}
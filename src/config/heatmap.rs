@@ -0,0 +1,23 @@
+use bevy::prelude::Resource;
+use std::path::PathBuf;
+
+#[derive(Resource)]
+pub struct HeatmapConfig {
+    pub visible: bool,
+    /// Cells across the room.
+    pub resolution: usize,
+    /// Seconds between texture refreshes while visible.
+    pub refresh_interval: f32,
+    pub export_dir: PathBuf,
+}
+
+impl Default for HeatmapConfig {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            resolution: 64,
+            refresh_interval: 0.5,
+            export_dir: PathBuf::from("."),
+        }
+    }
+}
@@ -1,9 +1,103 @@
-use bevy::prelude::Resource;
+use bevy::prelude::{Reflect, ReflectResource, Resource, Vec3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Resource)]
+use crate::components::Fixture;
+
+/// How lanterns are arranged when the scene is set up.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum LanternLayout {
+    /// Square grid covering the room.
+    Grid { spacing: f32 },
+    /// Evenly spaced around a circle centred on the room.
+    Ring { count: usize, radius: f32 },
+    /// A straight row through the centre of the room, like a street.
+    Line { count: usize, spacing: f32 },
+    /// Random positions that keep at least `min_spacing` apart.
+    Random { count: usize, min_spacing: f32 },
+    /// Groups of lanterns scattered around randomly placed centres.
+    Clusters {
+        clusters: usize,
+        per_cluster: usize,
+        spread: f32,
+    },
+    /// Exact lantern positions, each optionally with its own fixture.
+    Positions(Vec<LanternPlacement>),
+}
+
+/// A lantern in a `Positions` layout.
+#[derive(Reflect, Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct LanternPlacement {
+    pub position: Vec3,
+    /// Overrides `LanternConfig::fixture` for this lantern.
+    #[serde(default)]
+    pub fixture: Option<Fixture>,
+}
+
+impl From<Vec3> for LanternPlacement {
+    fn from(position: Vec3) -> Self {
+        Self {
+            position,
+            fixture: None,
+        }
+    }
+}
+
+/// The most lanterns a layout may ask for.
+pub const MAX_LANTERNS: usize = 1024;
+/// Grid and line spacing below this would crowd lanterns into each other.
+pub const MIN_LANTERN_SPACING: f32 = 1.0;
+
+impl LanternLayout {
+    /// Whether the layout can be built: every distance is finite, spacings
+    /// are at least `MIN_LANTERN_SPACING`, sizes that would stack lanterns up
+    /// are positive, fixture cones open, and no more than `MAX_LANTERNS`
+    /// lanterns are asked for.
+    pub fn is_valid(&self) -> bool {
+        let spaced = |spacing: f32| spacing.is_finite() && spacing >= MIN_LANTERN_SPACING;
+        let positive = |distance: f32| distance.is_finite() && distance > 0.0;
+        match self {
+            Self::Grid { spacing } => spaced(*spacing),
+            Self::Line { count, spacing } => *count <= MAX_LANTERNS && spaced(*spacing),
+            Self::Ring { count, radius } => *count <= MAX_LANTERNS && positive(*radius),
+            Self::Random { count, min_spacing } => {
+                *count <= MAX_LANTERNS && min_spacing.is_finite() && *min_spacing >= 0.0
+            }
+            Self::Clusters {
+                clusters,
+                per_cluster,
+                spread,
+            } => {
+                clusters
+                    .checked_mul(*per_cluster)
+                    .is_some_and(|count| count <= MAX_LANTERNS)
+                    && positive(*spread)
+            }
+            Self::Positions(placements) => {
+                placements.len() <= MAX_LANTERNS
+                    && placements.iter().all(|placement| {
+                        placement.position.is_finite()
+                            && placement.fixture.is_none_or(|fixture| fixture.is_valid())
+                    })
+            }
+        }
+    }
+}
+
+impl Default for LanternLayout {
+    fn default() -> Self {
+        Self::Grid { spacing: 5.0 }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 pub struct LanternConfig {
+    pub layout: LanternLayout,
+    pub fixture: Fixture,
     pub physical_radius: f32,
+    /// Chance per 1/60 s of simulated time that a lantern off cooldown turns on.
     pub on_chance: f64,
+    /// Chance per 1/60 s of simulated time that a lit lantern flickers.
     pub flicker_chance: f64,
     pub emissive_multiplier: f32,
     pub bob_speed: f32,
@@ -13,6 +107,8 @@ pub struct LanternConfig {
 impl Default for LanternConfig {
     fn default() -> Self {
         Self {
+            layout: LanternLayout::default(),
+            fixture: Fixture::default(),
             physical_radius: 1.0,
             on_chance: 0.01,
             flicker_chance: 0.01,
@@ -1,5 +1,19 @@
+pub mod benchmark;
+pub mod camera;
+pub mod heatmap;
 pub mod lantern;
 pub mod moth;
+pub mod recording;
+pub mod snapshot;
+pub mod trail;
+pub mod trap;
 
+pub use benchmark::*;
+pub use camera::*;
+pub use heatmap::*;
 pub use lantern::*;
 pub use moth::*;
+pub use recording::*;
+pub use snapshot::*;
+pub use trail::*;
+pub use trap::*;
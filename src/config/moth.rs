@@ -1,11 +1,24 @@
-use bevy::prelude::Resource;
+use bevy::prelude::{Reflect, ReflectResource, Resource};
 
-#[derive(Resource)]
+/// The most moths a share link may ask for, as many as the benchmark scene.
+pub const MAX_MOTHS: i32 = 10_000;
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 pub struct MothConfig {
     pub moth_count: i32,
     pub moth_speed: f32,
     pub view_radius: f32,
     pub attraction_factor: f32,
+    /// Moths flying into a surface slower than this, in metres per second
+    /// along its normal, land on it rather than bouncing off.
+    pub perch_speed: f32,
+    /// Shortest and longest rests, in seconds, once landed.
+    pub min_perch_secs: f32,
+    pub max_perch_secs: f32,
+    /// Wingbeats per second when flying at `moth_speed`, slowing with the
+    /// moth. Perched moths hold their wings still.
+    pub wingbeat_hz: f32,
 }
 
 impl Default for MothConfig {
@@ -15,6 +28,10 @@ impl Default for MothConfig {
             moth_speed: 1.0,
             view_radius: 15.0,
             attraction_factor: 0.8,
+            perch_speed: 0.4,
+            min_perch_secs: 2.0,
+            max_perch_secs: 12.0,
+            wingbeat_hz: 10.0,
         }
     }
 }
@@ -0,0 +1,21 @@
+use bevy::prelude::Resource;
+use std::path::PathBuf;
+
+#[derive(Resource)]
+pub struct RecordingConfig {
+    /// Where recordings are saved and loaded from. In the browser only the
+    /// file name is used, for the download.
+    pub path: PathBuf,
+    /// Simulated seconds between recorded frames. Playback interpolates
+    /// between them.
+    pub sample_interval: f32,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("recording.ron"),
+            sample_interval: 1.0 / 30.0,
+        }
+    }
+}
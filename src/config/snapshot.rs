@@ -0,0 +1,17 @@
+use bevy::prelude::Resource;
+use std::path::PathBuf;
+
+#[derive(Resource)]
+pub struct SnapshotConfig {
+    /// Where snapshots are saved and loaded from. In the browser only the
+    /// file name is used, for the download.
+    pub path: PathBuf,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("snapshot.scn.ron"),
+        }
+    }
+}
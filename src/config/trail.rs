@@ -0,0 +1,32 @@
+use bevy::prelude::Resource;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailColouring {
+    #[default]
+    Speed,
+    NearestLantern,
+}
+
+#[derive(Resource)]
+pub struct TrailConfig {
+    pub enabled: bool,
+    /// Only draw the trail of the selected moth.
+    pub selected_only: bool,
+    /// Number of points kept per moth.
+    pub length: usize,
+    /// Seconds between recorded points.
+    pub sample_interval: f32,
+    pub colouring: TrailColouring,
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            selected_only: false,
+            length: 120,
+            sample_interval: 0.05,
+            colouring: TrailColouring::default(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use bevy::prelude::Resource;
+use std::path::PathBuf;
+
+use crate::components::Fixture;
+use crate::config::LanternLayout;
+
+/// Light traps: lanterns with a bucket below them that catches moths. They're
+/// hung in addition to the usual lantern layout.
+#[derive(Resource)]
+pub struct TrapConfig {
+    pub layout: LanternLayout,
+    pub fixture: Fixture,
+    /// Radius of the funnel mouth, centred under the lantern.
+    pub capture_radius: f32,
+    /// How far below the lantern the bucket reaches.
+    pub capture_depth: f32,
+    /// Simulated seconds between points on each trap's catch curve.
+    pub sample_interval: f32,
+    pub export_path: PathBuf,
+}
+
+impl Default for TrapConfig {
+    fn default() -> Self {
+        Self {
+            layout: LanternLayout::Positions(Vec::new()),
+            fixture: Fixture::default(),
+            capture_radius: 0.8,
+            capture_depth: 1.5,
+            sample_interval: 1.0,
+            export_path: PathBuf::from("catches.csv"),
+        }
+    }
+}
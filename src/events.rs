@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+/// Sent when a lantern switches on.
+#[derive(Event, Copy, Clone, Debug)]
+pub struct LanternTurnedOn {
+    pub entity: Entity,
+    pub radiance: f32,
+    pub intensity: f32,
+}
+
+/// Sent when a lantern's on-timer runs out. Carries the radiance and
+/// intensity the lantern was running at before it switched off.
+#[derive(Event, Copy, Clone, Debug)]
+pub struct LanternTurnedOff {
+    pub entity: Entity,
+    pub radiance: f32,
+    pub intensity: f32,
+}
+
+/// Sent when an active lantern flickers away from its base intensity.
+#[derive(Event, Copy, Clone, Debug)]
+pub struct LanternFlickered {
+    pub entity: Entity,
+    pub radiance: f32,
+    pub intensity: f32,
+}
+
+/// Sent when a light trap catches a moth. The moth is despawned by then.
+#[derive(Event, Copy, Clone, Debug)]
+pub struct MothCaptured {
+    pub moth: Entity,
+    pub trap: Entity,
+}
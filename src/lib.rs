@@ -0,0 +1,16 @@
+pub mod api;
+pub mod components;
+pub mod config;
+pub mod events;
+pub mod recording;
+pub mod resources;
+pub mod setup;
+pub mod share;
+pub mod simulation;
+pub mod snapshot;
+pub mod sweep;
+pub mod systems;
+pub mod timeline;
+#[cfg(target_arch = "wasm32")]
+pub mod web_api;
+pub mod wings;
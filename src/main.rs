@@ -1,70 +1,231 @@
+use bevy::log::{Level, LogPlugin};
 use bevy::prelude::*;
+use bevy::time::TimeSystem;
+use bevy::window::PresentMode;
 use bevy_rand::prelude::{EntropyPlugin, WyRand};
 use std::panic;
+use std::path::PathBuf;
 
-use crate::config::{LanternConfig, MothConfig};
-use crate::resources::{SpatialIndex, TouchState};
-use crate::setup::{
-    setup_ceiling, setup_lantern_index, setup_lanterns, setup_lights_and_camera, setup_moths,
+use phototaxis::config::{
+    BenchmarkConfig, CameraConfig, HeatmapConfig, LanternConfig, LanternLayout, MothConfig,
+    RecordingConfig, SnapshotConfig, TrailConfig, TrapConfig,
 };
-use crate::systems::{
-    camera_control_system, enforce_boundary_system, lantern_bob_system, lantern_power_system,
-    moth_attraction_system, moth_collision_system, moth_movement_system, moth_wander_system,
+use phototaxis::resources::{
+    DebugOverlay, FrameTimes, LanternInteraction, MothSelection, ReplayRequests, SimulationSeed,
+    SnapshotRequests, TimeControl, TouchState,
 };
+use phototaxis::setup::{
+    setup_heatmap, setup_lights_and_camera, setup_moth_inspector, setup_replay_controls,
+    setup_share_button, setup_time_controls,
+};
+use phototaxis::share::ShareSettings;
+use phototaxis::simulation::{MothUpdate, SimulationPlugin};
+#[cfg(target_arch = "wasm32")]
+use phototaxis::systems::page_share_settings;
+use phototaxis::systems::{
+    camera_control_system, debug_overlay_system, debug_overlay_toggle_system, draw_trails_system,
+    follow_selected_moth_system, frame_time_report_system, heatmap_accumulate_system,
+    heatmap_keys_system, heatmap_render_system, lantern_click_system, lantern_delete_system,
+    lantern_drag_system, lantern_placement_system, moth_inspector_system, moth_selection_system,
+    record_trail_system, replay_button_system, replay_close_system, replay_keys_system,
+    replay_load_system, replay_playback_system, replay_scrubber_system, replay_ui_system,
+    replaying, selected_lantern_gizmo_system, share_link_system, simulation_advancing,
+    single_step_system, snapshot_keys_system, snapshot_system, time_control_button_system,
+    time_control_keys_system, time_control_label_system, trail_toggle_system, trap_keys_system,
+};
+use phototaxis::timeline::{Timeline, TimelinePlayer, TimelinePlugin};
+use phototaxis::wings::MothWingsPlugin;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
 
-mod components;
-mod config;
-mod resources;
-mod setup;
-mod systems;
+/// How the windowed app starts. The native command line fills this in; the
+/// web build reads its setup from the page URL.
+#[derive(Default)]
+struct WindowedOptions {
+    /// Seed, moth count, lanterns and camera. Anything left unset keeps its
+    /// default, and a missing seed is picked at random.
+    share: ShareSettings,
+    /// Snapshot text to restore once the scene is set up.
+    scenario: Option<String>,
+    /// Events to play during the run.
+    timeline: Option<Timeline>,
+    /// Where light traps hang, if anywhere.
+    traps: Option<LanternLayout>,
+    /// Recording text to open in the replay player.
+    replay: Option<String>,
+    /// Where snapshots, recordings and heatmap exports are saved.
+    output_dir: Option<PathBuf>,
+    log_level: Option<Level>,
+}
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
+    windowed_app(WindowedOptions {
+        share: page_share_settings(),
+        ..default()
+    })
+    .run();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    cli::main()
+}
+
+fn windowed_app(options: WindowedOptions) -> App {
+    // `PHOTOTAXIS_BENCHMARK=<moths>` swaps in a crowded scene, turns off vsync
+    // and logs frame times
+    let benchmark = BenchmarkConfig::from_env();
+    let mut moth_config = MothConfig::default();
+    let mut present_mode = PresentMode::default();
+    if let Some(benchmark) = &benchmark {
+        moth_config.moth_count = benchmark.moth_count;
+        present_mode = PresentMode::AutoNoVsync;
+    }
+    let mut lantern_config = LanternConfig::default();
+    let mut camera_config = CameraConfig::default();
+    options
+        .share
+        .apply(&mut moth_config, &mut lantern_config, &mut camera_config);
+
+    let mut snapshot_config = SnapshotConfig::default();
+    let mut recording_config = RecordingConfig::default();
+    let mut heatmap_config = HeatmapConfig::default();
+    let mut trap_config = TrapConfig::default();
+    if let Some(layout) = options.traps {
+        trap_config.layout = layout;
+    }
+    if let Some(dir) = options.output_dir {
+        snapshot_config.path = dir.join(&snapshot_config.path);
+        recording_config.path = dir.join(&recording_config.path);
+        trap_config.export_path = dir.join(&trap_config.export_path);
+        heatmap_config.export_dir = dir;
+    }
+
+    // Files given on the command line are picked up by the same systems that
+    // load them from the keyboard controls
+    let snapshot_requests = SnapshotRequests::default();
+    *snapshot_requests.load.lock().unwrap() = options.scenario;
+    let replay_requests = ReplayRequests::default();
+    *replay_requests.load.lock().unwrap() = options.replay;
+
+    // Always seeded, so a share link can recreate the run
+    let seed = options.share.seed.unwrap_or_else(rand::random);
 
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Phototaxis".into(),
                     canvas: Some("#bevy".to_owned()),
                     fit_canvas_to_parent: true,
+                    present_mode,
                     ..default()
                 }),
                 ..default()
+            })
+            .set(match options.log_level {
+                Some(level) => LogPlugin { level, ..default() },
+                None => LogPlugin::default(),
             }),
-            EntropyPlugin::<WyRand>::default(),
-        ))
-        .init_resource::<SpatialIndex>()
-        .init_resource::<TouchState>()
-        .insert_resource(MothConfig::default())
-        .insert_resource(LanternConfig::default())
-        .insert_resource(ClearColor(Color::srgb(0.01, 0.01, 0.08)))
-        .add_systems(
-            Startup,
+        MeshPickingPlugin,
+        EntropyPlugin::<WyRand>::with_seed(seed.to_le_bytes()),
+        SimulationPlugin,
+        MothWingsPlugin,
+    ))
+    .init_resource::<TouchState>()
+    .init_resource::<LanternInteraction>()
+    .init_resource::<MothSelection>()
+    .init_resource::<DebugOverlay>()
+    .init_resource::<TimeControl>()
+    .insert_resource(snapshot_requests)
+    .insert_resource(replay_requests)
+    .insert_resource(moth_config)
+    .insert_resource(SimulationSeed(seed))
+    .insert_resource(lantern_config)
+    .insert_resource(trap_config)
+    .insert_resource(camera_config)
+    .insert_resource(TrailConfig::default())
+    .insert_resource(heatmap_config)
+    .insert_resource(snapshot_config)
+    .insert_resource(recording_config)
+    .insert_resource(ClearColor(Color::srgb(0.01, 0.01, 0.08)))
+    .add_systems(
+        Startup,
+        (
+            setup_lights_and_camera,
+            setup_moth_inspector,
+            setup_heatmap,
+            setup_time_controls,
+            setup_replay_controls,
+            setup_share_button,
+        ),
+    )
+    .add_systems(First, single_step_system.after(TimeSystem))
+    .add_systems(
+        Update,
+        (
+            (record_trail_system, heatmap_accumulate_system)
+                .after(MothUpdate)
+                .run_if(simulation_advancing),
             (
-                setup_ceiling,
-                setup_lights_and_camera,
-                setup_lanterns,
-                setup_moths,
-                setup_lantern_index,
+                moth_selection_system,
+                follow_selected_moth_system,
+                camera_control_system,
             )
                 .chain(),
-        )
-        .add_systems(
-            Update,
+            moth_inspector_system,
             (
-                (
-                    moth_wander_system,
-                    moth_attraction_system,
-                    moth_collision_system,
-                    enforce_boundary_system,
-                )
-                    .chain(),
-                moth_movement_system,
-                lantern_power_system,
-                lantern_bob_system,
-                camera_control_system,
-            ),
-        )
-        .run();
+                lantern_click_system,
+                lantern_placement_system,
+                lantern_delete_system,
+                lantern_drag_system,
+            )
+                .chain()
+                .run_if(not(replaying)),
+            selected_lantern_gizmo_system,
+            (trail_toggle_system, draw_trails_system).chain(),
+            (debug_overlay_toggle_system, debug_overlay_system).chain(),
+            (heatmap_keys_system, heatmap_render_system).chain(),
+            (snapshot_keys_system, snapshot_system).chain(),
+            (
+                time_control_keys_system,
+                time_control_button_system,
+                time_control_label_system,
+            )
+                .chain(),
+            share_link_system,
+            trap_keys_system,
+            (
+                replay_keys_system,
+                replay_button_system,
+                replay_scrubber_system,
+                replay_close_system,
+                replay_load_system,
+                replay_playback_system,
+                replay_ui_system,
+            )
+                .chain()
+                .before(record_trail_system),
+        ),
+    );
+
+    #[cfg(target_arch = "wasm32")]
+    app.add_plugins(phototaxis::web_api::WebApiPlugin);
+    app.add_plugins(TimelinePlugin);
+    if let Some(timeline) = options.timeline {
+        app.insert_resource(TimelinePlayer::new(timeline));
+    }
+
+    if let Some(benchmark) = benchmark {
+        app.insert_resource(benchmark)
+            .init_resource::<FrameTimes>()
+            .add_systems(Update, frame_time_report_system);
+    }
+
+    app
 }
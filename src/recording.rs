@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy::scene::ron;
+use serde::{Deserialize, Serialize};
+
+use crate::components::Fixture;
+
+/// Moth and lantern positions sampled over a run, plus the lantern switches
+/// in between, for playback without re-simulating.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+    pub events: Vec<RecordedEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedFrame {
+    /// Simulated seconds since recording started.
+    pub time: f32,
+    /// Moth positions keyed by recording id, in ascending id order.
+    pub moths: Vec<(u32, Vec3)>,
+    pub lanterns: Vec<RecordedLantern>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct RecordedLantern {
+    pub id: u32,
+    pub position: Vec3,
+    pub fixture: Fixture,
+    /// Light intensity, zero while the lantern is off.
+    pub intensity: f32,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LanternEventKind {
+    TurnedOn,
+    TurnedOff,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct RecordedEvent {
+    pub time: f32,
+    pub lantern: u32,
+    pub kind: LanternEventKind,
+}
+
+impl Recording {
+    pub fn to_ron(&self) -> Result<String> {
+        Ok(ron::ser::to_string(self)?)
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self> {
+        Ok(ron::de::from_str(text)?)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    /// The frames either side of `time` and how far between them it falls.
+    pub fn frames_around(&self, time: f32) -> Option<(&RecordedFrame, &RecordedFrame, f32)> {
+        let next = self
+            .frames
+            .partition_point(|frame| frame.time <= time)
+            .clamp(1, self.frames.len().max(1));
+        let before = self.frames.get(next - 1)?;
+        let after = self.frames.get(next).unwrap_or(before);
+        let span = after.time - before.time;
+        let t = if span > 0.0 {
+            ((time - before.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Some((before, after, t))
+    }
+
+    /// The first turn-on strictly after `time`.
+    pub fn next_turn_on(&self, time: f32) -> Option<&RecordedEvent> {
+        self.events
+            .iter()
+            .find(|event| event.kind == LanternEventKind::TurnedOn && event.time > time)
+    }
+
+    /// The last turn-on strictly before `time`.
+    pub fn previous_turn_on(&self, time: f32) -> Option<&RecordedEvent> {
+        self.events
+            .iter()
+            .rev()
+            .find(|event| event.kind == LanternEventKind::TurnedOn && event.time < time)
+    }
+}
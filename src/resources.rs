@@ -1,21 +1,70 @@
 use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use crate::components::LanternId;
+use crate::recording::Recording;
+use crate::wings::{MothMaterial, MothWings, moth_mesh};
 
 /// Cell size should be adjusted based on the average size of entities
 /// and the view radius of the moths.
 pub const CELL_SIZE: f32 = 15.0;
 
+/// Lanterns bucketed by where they are on the floor plane. Positions are a
+/// translation's `xz()`, since lanterns spread out across the room rather than
+/// up it.
 #[derive(Resource, Default)]
 pub struct SpatialIndex {
-    pub map: HashMap<(i32, i32), HashSet<Entity>>,
+    map: HashMap<(i32, i32), HashSet<Entity>>,
+    /// The cell each indexed entity is in, so removal doesn't scan every cell.
+    cells: HashMap<Entity, (i32, i32)>,
 }
 
 impl SpatialIndex {
-    pub fn get_nearby(&self, pos: Vec2) -> Vec<Entity> {
-        let tile = (
+    /// The cell containing floor-plane position `pos` (x, z).
+    pub fn tile(pos: Vec2) -> (i32, i32) {
+        (
             (pos.x / CELL_SIZE).floor() as i32,
             (pos.y / CELL_SIZE).floor() as i32,
-        );
+        )
+    }
+
+    /// Inserts an entity at `pos`, moving it out of any cell it was already in.
+    pub fn insert(&mut self, entity: Entity, pos: Vec2) {
+        self.remove(entity);
+        let tile = Self::tile(pos);
+        self.map.entry(tile).or_default().insert(entity);
+        self.cells.insert(entity, tile);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(tile) = self.cells.remove(&entity)
+            && let Some(entities) = self.map.get_mut(&tile)
+        {
+            entities.remove(&entity);
+            if entities.is_empty() {
+                self.map.remove(&tile);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.cells.clear();
+    }
+
+    /// The cell `entity` was last inserted into, if it is indexed.
+    pub fn cell_of(&self, entity: Entity) -> Option<(i32, i32)> {
+        self.cells.get(&entity).copied()
+    }
+
+    /// Every non-empty cell and the entities in it.
+    pub fn cells(&self) -> impl Iterator<Item = (&(i32, i32), &HashSet<Entity>)> {
+        self.map.iter()
+    }
+
+    pub fn get_nearby(&self, pos: Vec2) -> Vec<Entity> {
+        let tile = Self::tile(pos);
         let mut nearby = Vec::new();
         for x in -1..=1 {
             for y in -1..=1 {
@@ -28,8 +77,253 @@ impl SpatialIndex {
     }
 }
 
+/// The cylindrical room moths are kept inside.
+#[derive(Resource, Reflect, Copy, Clone)]
+#[reflect(Resource)]
+pub struct RoomBounds {
+    pub radius: f32,
+    pub floor: f32,
+    pub ceiling: f32,
+}
+
+impl Default for RoomBounds {
+    fn default() -> Self {
+        Self {
+            radius: 10.0,
+            floor: 0.0,
+            ceiling: 10.0,
+        }
+    }
+}
+
+/// The seed every random number generator was started from, so a share link
+/// can recreate the run.
+#[derive(Resource, Copy, Clone)]
+pub struct SimulationSeed(pub u64);
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
 #[derive(Resource, Default)]
 pub struct TouchState {
     pub start_pos: Option<Vec2>,
     pub last_pos: Option<Vec2>,
 }
+
+#[derive(Resource, Default)]
+pub struct LanternInteraction {
+    pub selected: Option<Entity>,
+    /// Entities currently being dragged by a pointer, including the window
+    /// when the drag started over empty space.
+    pub dragging: HashSet<Entity>,
+}
+
+#[derive(Resource, Default)]
+pub struct MothSelection {
+    /// The moth the camera follows and the inspector describes.
+    pub selected: Option<Entity>,
+}
+
+/// A 2D histogram over the unit square, stored row-major with row 0 first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DensityGrid {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<f32>,
+}
+
+impl DensityGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height],
+        }
+    }
+
+    /// Adds `weight` to the cell containing `(u, v)`. Points outside
+    /// `[0, 1]` are ignored.
+    pub fn add(&mut self, u: f32, v: f32, weight: f32) {
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return;
+        }
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        self.cells[y * self.width + x] += weight;
+    }
+
+    pub fn max(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(0.0);
+    }
+}
+
+/// Accumulated moth-seconds spent in each part of the room.
+#[derive(Resource)]
+pub struct Heatmap {
+    /// Looking down: x across, z down.
+    pub top: DensityGrid,
+    /// Looking from the front: x across, height down from the ceiling.
+    pub side: DensityGrid,
+}
+
+impl Heatmap {
+    pub fn new(resolution: usize, bounds: &RoomBounds) -> Self {
+        let aspect = (bounds.ceiling - bounds.floor) / (bounds.radius * 2.0);
+        let side_height = ((resolution as f32 * aspect).round() as usize).max(1);
+        Self {
+            top: DensityGrid::new(resolution, resolution),
+            side: DensityGrid::new(resolution, side_height),
+        }
+    }
+
+    pub fn record(&mut self, pos: Vec3, bounds: &RoomBounds, weight: f32) {
+        let u = (pos.x + bounds.radius) / (bounds.radius * 2.0);
+        let v_top = (pos.z + bounds.radius) / (bounds.radius * 2.0);
+        let v_side = (bounds.ceiling - pos.y) / (bounds.ceiling - bounds.floor);
+        self.top.add(u, v_top, weight);
+        self.side.add(u, v_side, weight);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct TimeControl {
+    /// Set while paused to advance the simulation by one fixed timestep on
+    /// the next frame.
+    pub step_requested: bool,
+}
+
+/// Frame times collected since the last benchmark report.
+#[derive(Resource, Default)]
+pub struct FrameTimes {
+    pub samples: Vec<f32>,
+    pub since_report: f32,
+}
+
+/// The mesh and material every moth shares.
+#[derive(Resource, Clone)]
+pub struct MothAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<MothMaterial>,
+}
+
+impl MothAssets {
+    pub fn new(meshes: &mut Assets<Mesh>, materials: &mut Assets<MothMaterial>) -> Self {
+        Self {
+            mesh: meshes.add(moth_mesh()),
+            material: materials.add(MothMaterial {
+                base: StandardMaterial {
+                    base_color: Color::srgb(1.0, 1.0, 1.0),
+                    emissive: Color::srgb(1.0, 1.0, 1.0).to_linear() * 10.0,
+                    // The wings are single faces, seen from above and below
+                    cull_mode: None,
+                    double_sided: true,
+                    ..default()
+                },
+                extension: MothWings::default(),
+            }),
+        }
+    }
+}
+
+impl FromWorld for MothAssets {
+    fn from_world(world: &mut World) -> Self {
+        world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+            Self::new(
+                &mut meshes,
+                &mut world.resource_mut::<Assets<MothMaterial>>(),
+            )
+        })
+    }
+}
+
+/// The `LanternId` the next lantern gets. Saved with snapshots so lanterns
+/// spawned after a restore don't take the id of one that was deleted.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct NextLanternId(pub u32);
+
+impl NextLanternId {
+    pub fn issue(&mut self) -> LanternId {
+        let id = LanternId(self.0);
+        self.0 += 1;
+        id
+    }
+}
+
+/// Saved alongside a snapshot so bobbing lanterns resume where they were.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct SnapshotInfo {
+    pub elapsed_secs: f32,
+}
+
+/// Snapshot work queued by the controls for the exclusive snapshot system.
+#[derive(Resource, Default)]
+pub struct SnapshotRequests {
+    pub save: bool,
+    /// Snapshot text waiting to be restored. Shared so a browser file read can
+    /// hand it over when it completes.
+    pub load: Arc<Mutex<Option<String>>>,
+}
+
+/// Builds a recording from the live simulation while `active`.
+#[derive(Resource, Default)]
+pub struct Recorder {
+    pub active: bool,
+    pub recording: Recording,
+    /// Simulated seconds since recording started.
+    pub elapsed: f32,
+    pub since_sample: f32,
+    /// Stable ids for the entities seen so far, in the order they appeared.
+    pub moth_ids: HashMap<Entity, u32>,
+    pub lantern_ids: HashMap<Entity, u32>,
+}
+
+impl Recorder {
+    pub fn start(&mut self) {
+        *self = Self {
+            active: true,
+            ..default()
+        };
+    }
+
+    /// Stops recording and hands back what was captured.
+    pub fn stop(&mut self) -> Recording {
+        self.active = false;
+        std::mem::take(&mut self.recording)
+    }
+
+    pub fn id(ids: &mut HashMap<Entity, u32>, entity: Entity) -> u32 {
+        let next = ids.len() as u32;
+        *ids.entry(entity).or_insert(next)
+    }
+}
+
+/// The recording being played back, if any. While it is loaded the
+/// simulation systems stand down and moths and lanterns follow the recording.
+#[derive(Resource, Default)]
+pub struct ReplayPlayer {
+    pub recording: Option<Recording>,
+    /// Seconds into the recording.
+    pub time: f32,
+    /// The entity standing in for each recorded id.
+    pub moths: HashMap<u32, Entity>,
+    pub lanterns: HashMap<u32, Entity>,
+    /// The live simulation, saved when playback started and put back when it
+    /// closes.
+    pub saved_scene: Option<String>,
+}
+
+/// Recording work queued by the controls.
+#[derive(Resource, Default)]
+pub struct ReplayRequests {
+    /// Recording text waiting to be played. Shared so a browser file read can
+    /// hand it over when it completes.
+    pub load: Arc<Mutex<Option<String>>>,
+}
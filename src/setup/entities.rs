@@ -1,98 +1,270 @@
+use bevy::ecs::bundle::NoBundleEffect;
 use bevy::prelude::*;
-use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use bevy::render::mesh::MeshTag;
+use bevy_rand::prelude::{ForkableRng, GlobalEntropy, WyRand};
 use rand::Rng;
 
-use crate::components::{Ceiling, Lantern, LanternBob, Moth, Velocity};
-use crate::config::MothConfig;
+use crate::components::{
+    Ceiling, Fixture, Lantern, LanternBob, LanternId, LightTrap, Moth, MothPerception, MothState,
+    MothStats, Steering, Trail, Velocity,
+};
+use crate::config::{LanternConfig, MothConfig, TrapConfig};
+use crate::resources::{MothAssets, NextLanternId, RoomBounds};
+use crate::setup::lantern_placements;
+use crate::wings::MothMaterial;
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_lanterns(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     ceiling_query: Query<&Transform, With<Ceiling>>,
+    config: Res<LanternConfig>,
+    bounds: Res<RoomBounds>,
+    mut lantern_ids: ResMut<NextLanternId>,
     mut rng: GlobalEntropy<WyRand>,
 ) {
-    const LANTERN_SPACING: f32 = 5.0;
-    const ROOM_RADIUS: f32 = 10.0;
-
     if let Ok(ceiling_transform) = ceiling_query.single() {
-        let num_x = (ROOM_RADIUS * 2.0 / LANTERN_SPACING).floor() as i32;
-        let num_z = (ROOM_RADIUS * 2.0 / LANTERN_SPACING).floor() as i32;
-
-        for i in 0..num_x {
-            for j in 0..num_z {
-                let x = (i as f32 - num_x as f32 / 2.0 + 0.5) * LANTERN_SPACING;
-                let z = (j as f32 - num_z as f32 / 2.0 + 0.5) * LANTERN_SPACING;
-                let y = ceiling_transform.translation.y - rng.random_range(1.0..5.0);
-
-                let lantern_glow_color = Color::srgb(1.0, 0.5, 0.0);
-
-                let mut cooldown = Timer::from_seconds(10.0, TimerMode::Once);
-                cooldown.tick(cooldown.duration());
-
-                commands.spawn((
-                    Mesh3d(meshes.add(Cuboid::new(0.5, 1.0, 0.5))),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color: Color::srgb(0.8, 0.7, 0.6),
-                        ..default()
-                    })),
-                    PointLight {
-                        intensity: 0.0,
-                        shadows_enabled: true,
-                        color: lantern_glow_color,
-                        ..default()
-                    },
-                    Transform::from_xyz(x, y, z),
-                    Lantern {
-                        radiance: 0.0,
-                        is_on: false,
-                        on_timer: Timer::from_seconds(
-                            rng.random_range(5.0..=20.0),
-                            TimerMode::Once,
-                        ),
-                        cooldown,
-                        ..default()
-                    },
-                    LanternBob {
-                        initial_y: y,
-                        phase_offset: rng.random_range(0.0..std::f32::consts::TAU),
-                    },
-                ));
-            }
+        let placements = lantern_placements(
+            &config.layout,
+            bounds.radius,
+            ceiling_transform.translation.y,
+            &mut rng,
+        );
+
+        for placement in placements {
+            spawn_lantern(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                lantern_ids.issue(),
+                placement.position,
+                placement.fixture.unwrap_or(config.fixture),
+                &mut rng,
+            );
         }
     }
 }
 
-pub fn setup_moths(
+/// Hangs the light traps, which are lanterns like any other apart from the
+/// bucket below them.
+#[allow(clippy::too_many_arguments)]
+pub fn setup_traps(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    ceiling_query: Query<&Transform, With<Ceiling>>,
+    config: Res<TrapConfig>,
+    bounds: Res<RoomBounds>,
+    mut lantern_ids: ResMut<NextLanternId>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    let Ok(ceiling_transform) = ceiling_query.single() else {
+        return;
+    };
+    let placements = lantern_placements(
+        &config.layout,
+        bounds.radius,
+        ceiling_transform.translation.y,
+        &mut rng,
+    );
+
+    for placement in placements {
+        let fixture = placement.fixture.unwrap_or(config.fixture);
+        let entity = spawn_lantern(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            lantern_ids.issue(),
+            placement.position,
+            fixture,
+            &mut rng,
+        );
+        let trap = LightTrap {
+            capture_radius: config.capture_radius,
+            capture_depth: config.capture_depth,
+            ..default()
+        };
+        insert_trap_visuals(
+            &mut commands.entity(entity),
+            &mut meshes,
+            &mut materials,
+            &trap,
+            fixture,
+        );
+        commands.entity(entity).insert(trap);
+    }
+}
+
+pub fn spawn_lantern(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    id: LanternId,
+    position: Vec3,
+    fixture: Fixture,
+    rng: &mut GlobalEntropy<WyRand>,
+) -> Entity {
+    let mut cooldown = Timer::from_seconds(10.0, TimerMode::Once);
+    cooldown.tick(cooldown.duration());
+
+    // Spot lights shine along the transform's forward axis, so directional
+    // fixtures are rotated to point along their cone
+    let mut transform = Transform::from_translation(position);
+    if let Some((axis, _)) = fixture.cone() {
+        transform.look_to(axis, Vec3::Z);
+    }
+
+    let mut lantern = commands.spawn((
+        transform,
+        id,
+        Lantern {
+            fixture,
+            radiance: 0.0,
+            is_on: false,
+            on_timer: Timer::from_seconds(rng.random_range(5.0..=20.0), TimerMode::Once),
+            cooldown,
+            ..default()
+        },
+        LanternBob {
+            initial_y: position.y,
+            phase_offset: rng.random_range(0.0..std::f32::consts::TAU),
+        },
+        // Its own generator for switching and flickering, like a moth's
+        rng.fork_rng(),
+    ));
+    insert_lantern_visuals(&mut lantern, meshes, materials, fixture);
+
+    lantern.id()
+}
+
+/// Adds the body and an unlit light for `fixture` to a lantern entity.
+/// Directional fixtures get a body whose long side is local Z so it stays
+/// upright once the lantern faces along its cone.
+pub fn insert_lantern_visuals(
+    lantern: &mut EntityCommands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    fixture: Fixture,
+) {
+    let lantern_glow_color = Color::srgb(1.0, 0.5, 0.0);
+
+    let body = match fixture.cone() {
+        Some(_) => Cuboid::new(0.5, 0.5, 1.0),
+        None => Cuboid::new(0.5, 1.0, 0.5),
+    };
+    lantern.insert((
+        Mesh3d(meshes.add(body)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.7, 0.6),
+            ..default()
+        })),
+    ));
+
+    match fixture.cone() {
+        Some((_, cone_angle)) => lantern.insert(SpotLight {
+            intensity: 0.0,
+            shadows_enabled: true,
+            color: lantern_glow_color,
+            outer_angle: cone_angle,
+            inner_angle: cone_angle * 0.75,
+            ..default()
+        }),
+        None => lantern.insert(PointLight {
+            intensity: 0.0,
+            shadows_enabled: true,
+            color: lantern_glow_color,
+            ..default()
+        }),
+    };
+}
+
+/// Hangs the funnel and bucket of `trap` below a lantern with `fixture`.
+pub fn insert_trap_visuals(
+    lantern: &mut EntityCommands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    trap: &LightTrap,
+    fixture: Fixture,
+) {
+    // The bucket hangs straight down however the lantern is turned
+    let lantern_rotation = match fixture.cone() {
+        Some((axis, _)) => Transform::default().looking_to(axis, Vec3::Z).rotation,
+        None => Quat::IDENTITY,
+    };
+    let rotation = lantern_rotation.inverse();
+
+    lantern.with_child((
+        Mesh3d(meshes.add(ConicalFrustum {
+            radius_top: trap.capture_radius,
+            radius_bottom: trap.capture_radius * 0.6,
+            height: trap.capture_depth,
+        })),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.6, 0.7, 0.8, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })),
+        Transform::from_translation(rotation * Vec3::new(0.0, -trap.capture_depth / 2.0, 0.0))
+            .with_rotation(rotation),
+        Pickable::IGNORE,
+    ));
+}
+
+pub fn setup_moths(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<MothMaterial>>,
     config: Res<MothConfig>,
     mut rng: GlobalEntropy<WyRand>,
 ) {
-    for _ in 0..config.moth_count {
-        commands.spawn((
-            Mesh3d(meshes.add(Cone::new(0.05, 0.1))),
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::srgb(1.0, 1.0, 1.0),
-                emissive: Color::srgb(1.0, 1.0, 1.0).to_linear() * 10.0,
-                ..default()
-            })),
-            Transform::from_xyz(
+    // Every moth shares one mesh and material so the renderer can draw them
+    // all as instances of a single batch
+    let assets = MothAssets::new(&mut meshes, &mut materials);
+
+    let moths: Vec<_> = (0..config.moth_count)
+        .map(|_| {
+            let position = Vec3::new(
                 rng.random_range(-5.0..5.0),
                 rng.random_range(1.0..4.0),
                 rng.random_range(-5.0..5.0),
-            ),
-            Moth,
-            Velocity(
-                Vec3::new(
-                    rng.random_range(-1.0..1.0),
-                    rng.random_range(-1.0..1.0),
-                    rng.random_range(-1.0..1.0),
-                )
-                .normalize_or_zero()
-                    * config.moth_speed,
-            ),
-        ));
-    }
+            );
+            let velocity = Vec3::new(
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+            )
+            .normalize_or_zero()
+                * config.moth_speed;
+            moth_bundle(&assets, position, velocity, &mut rng)
+        })
+        .collect();
+    commands.spawn_batch(moths);
+    commands.insert_resource(assets);
+}
+
+/// Everything a moth is spawned with.
+pub fn moth_bundle(
+    assets: &MothAssets,
+    position: Vec3,
+    velocity: Vec3,
+    rng: &mut GlobalEntropy<WyRand>,
+) -> impl Bundle<Effect: NoBundleEffect> + use<> {
+    (
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(assets.material.clone()),
+        // Wingbeat phase, for the moth shader
+        MeshTag::default(),
+        Transform::from_translation(position),
+        Moth,
+        MothState::default(),
+        MothPerception::default(),
+        MothStats::default(),
+        Steering::default(),
+        Trail::default(),
+        Velocity(velocity),
+        // Each moth gets its own generator so moth systems can run in
+        // parallel without contending for the global one
+        rng.fork_rng(),
+    )
 }
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use rand::Rng;
+
+use crate::config::{LanternLayout, LanternPlacement};
+
+const MAX_PLACEMENT_ATTEMPTS: usize = 30;
+
+fn hang(xz: Vec2, ceiling_y: f32, rng: &mut GlobalEntropy<WyRand>) -> Vec3 {
+    Vec3::new(xz.x, ceiling_y - rng.random_range(1.0..5.0), xz.y)
+}
+
+fn random_in_disk(radius: f32, rng: &mut GlobalEntropy<WyRand>) -> Vec2 {
+    // sqrt keeps the distribution uniform over the area rather than bunched at the centre
+    let r = radius * rng.random_range(0.0f32..1.0).sqrt();
+    let theta = rng.random_range(0.0..std::f32::consts::TAU);
+    Vec2::from_angle(theta) * r
+}
+
+/// Dart throwing: sample the disk and reject anything closer than
+/// `min_spacing` to an accepted point. Gives up on a point after
+/// `MAX_PLACEMENT_ATTEMPTS` misses, so crowded layouts return fewer points.
+fn spaced_points(
+    count: usize,
+    radius: f32,
+    min_spacing: f32,
+    rng: &mut GlobalEntropy<WyRand>,
+) -> Vec<Vec2> {
+    let mut points: Vec<Vec2> = Vec::with_capacity(count);
+    for _ in 0..count {
+        for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+            let candidate = random_in_disk(radius, rng);
+            if points
+                .iter()
+                .all(|p| p.distance_squared(candidate) >= min_spacing.powi(2))
+            {
+                points.push(candidate);
+                break;
+            }
+        }
+    }
+    points
+}
+
+/// Where the layout puts its lanterns. Only `Positions` layouts pick
+/// fixtures; every other placement leaves it to `LanternConfig::fixture`.
+pub fn lantern_placements(
+    layout: &LanternLayout,
+    room_radius: f32,
+    ceiling_y: f32,
+    rng: &mut GlobalEntropy<WyRand>,
+) -> Vec<LanternPlacement> {
+    if !layout.is_valid() {
+        warn!("Ignoring lantern layout {layout:?}, which is too dense or has a bad distance");
+        return Vec::new();
+    }
+
+    let positions = match layout {
+        LanternLayout::Grid { spacing } => {
+            let num = (room_radius * 2.0 / spacing).floor() as i32;
+            let mut positions = Vec::new();
+            for i in 0..num {
+                for j in 0..num {
+                    let x = (i as f32 - num as f32 / 2.0 + 0.5) * spacing;
+                    let z = (j as f32 - num as f32 / 2.0 + 0.5) * spacing;
+                    positions.push(hang(Vec2::new(x, z), ceiling_y, rng));
+                }
+            }
+            positions
+        }
+        LanternLayout::Ring { count, radius } => (0..*count)
+            .map(|i| {
+                let theta = i as f32 / *count as f32 * std::f32::consts::TAU;
+                hang(Vec2::from_angle(theta) * *radius, ceiling_y, rng)
+            })
+            .collect(),
+        LanternLayout::Line { count, spacing } => (0..*count)
+            .map(|i| {
+                let x = (i as f32 - (*count as f32 - 1.0) / 2.0) * spacing;
+                hang(Vec2::new(x, 0.0), ceiling_y, rng)
+            })
+            .collect(),
+        LanternLayout::Random { count, min_spacing } => {
+            spaced_points(*count, room_radius, *min_spacing, rng)
+                .into_iter()
+                .map(|xz| hang(xz, ceiling_y, rng))
+                .collect()
+        }
+        LanternLayout::Clusters {
+            clusters,
+            per_cluster,
+            spread,
+        } => {
+            let centres = spaced_points(
+                *clusters,
+                (room_radius - spread).max(0.0),
+                spread * 2.0,
+                rng,
+            );
+            let mut positions = Vec::new();
+            for centre in centres {
+                for _ in 0..*per_cluster {
+                    let xz = (centre + random_in_disk(*spread, rng)).clamp_length_max(room_radius);
+                    positions.push(hang(xz, ceiling_y, rng));
+                }
+            }
+            positions
+        }
+        LanternLayout::Positions(placements) => return placements.clone(),
+    };
+    positions.into_iter().map(LanternPlacement::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy_rand::prelude::EntropyPlugin;
+
+    use super::*;
+    use crate::components::Fixture;
+    use crate::config::{MAX_LANTERNS, MIN_LANTERN_SPACING};
+
+    fn placements(layout: LanternLayout) -> Vec<LanternPlacement> {
+        let mut app = App::new();
+        app.add_plugins(EntropyPlugin::<WyRand>::with_seed(7u64.to_le_bytes()));
+        app.world_mut()
+            .run_system_once(move |mut rng: GlobalEntropy<WyRand>| {
+                lantern_placements(&layout, 10.0, 10.0, &mut rng)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn layouts_place_the_lanterns_they_ask_for() {
+        assert_eq!(placements(LanternLayout::Grid { spacing: 5.0 }).len(), 16);
+        assert_eq!(
+            placements(LanternLayout::Ring {
+                count: 6,
+                radius: 5.0
+            })
+            .len(),
+            6
+        );
+        assert_eq!(
+            placements(LanternLayout::Clusters {
+                clusters: 2,
+                per_cluster: 3,
+                spread: 1.0
+            })
+            .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn positions_keep_their_own_fixtures() {
+        let downlight = LanternPlacement {
+            position: Vec3::new(1.0, 6.0, 0.0),
+            fixture: Some(Fixture::Downlight { cone_angle: 0.5 }),
+        };
+        let default = LanternPlacement::from(Vec3::new(-1.0, 6.0, 0.0));
+        assert_eq!(
+            placements(LanternLayout::Positions(vec![downlight, default])),
+            [downlight, default]
+        );
+    }
+
+    #[test]
+    fn bad_distances_and_counts_are_rejected() {
+        let too_many = MAX_LANTERNS + 1;
+        for layout in [
+            LanternLayout::Grid { spacing: 0.0 },
+            LanternLayout::Grid { spacing: -2.0 },
+            LanternLayout::Grid { spacing: f32::NAN },
+            LanternLayout::Grid {
+                spacing: MIN_LANTERN_SPACING / 2.0,
+            },
+            LanternLayout::Ring {
+                count: 6,
+                radius: 0.0,
+            },
+            LanternLayout::Ring {
+                count: too_many,
+                radius: 5.0,
+            },
+            LanternLayout::Line {
+                count: 3,
+                spacing: -1.0,
+            },
+            LanternLayout::Random {
+                count: 4,
+                min_spacing: -1.0,
+            },
+            LanternLayout::Random {
+                count: too_many,
+                min_spacing: 0.0,
+            },
+            LanternLayout::Clusters {
+                clusters: 2,
+                per_cluster: 3,
+                spread: 0.0,
+            },
+            LanternLayout::Clusters {
+                clusters: usize::MAX,
+                per_cluster: 2,
+                spread: 1.0,
+            },
+            LanternLayout::Positions(vec![Vec3::new(1.0, f32::INFINITY, 0.0).into()]),
+            LanternLayout::Positions(vec![LanternPlacement {
+                position: Vec3::ZERO,
+                fixture: Some(Fixture::Downlight {
+                    cone_angle: f32::NAN,
+                }),
+            }]),
+        ] {
+            assert!(!layout.is_valid(), "{layout:?}");
+            assert!(placements(layout).is_empty());
+        }
+
+        assert!(
+            LanternLayout::Random {
+                count: 4,
+                min_spacing: 0.0
+            }
+            .is_valid()
+        );
+    }
+}
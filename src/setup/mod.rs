@@ -1,7 +1,11 @@
 pub mod entities;
+pub mod layout;
 pub mod scene;
 pub mod spatial_index;
+pub mod ui;
 
 pub use entities::*;
+pub use layout::*;
 pub use scene::*;
 pub use spatial_index::*;
+pub use ui::*;
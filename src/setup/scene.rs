@@ -1,7 +1,14 @@
 use bevy::core_pipeline::bloom::Bloom;
+use bevy::image::ImageSampler;
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 
-use crate::components::{Ceiling, OrbitCamera};
+use crate::components::{
+    CameraMode, CameraRig, Ceiling, HeatmapProjection, HeatmapSurface, OrbitCamera,
+};
+use crate::config::{CameraConfig, HeatmapConfig};
+use crate::resources::{DensityGrid, Heatmap, RoomBounds};
 
 pub fn setup_ceiling(mut commands: Commands) {
     commands.spawn((
@@ -11,15 +18,19 @@ pub fn setup_ceiling(mut commands: Commands) {
     ));
 }
 
-pub fn setup_lights_and_camera(mut commands: Commands) {
+pub fn setup_lights_and_camera(mut commands: Commands, config: Res<CameraConfig>) {
     commands.insert_resource(AmbientLight {
         color: Color::srgb(0.1, 0.1, 0.3),
         brightness: 0.05,
         ..default()
     });
 
-    let initial_radius = 18.0;
-    let initial_angle = std::f32::consts::FRAC_PI_2;
+    let rig = CameraRig {
+        focus: config.focus,
+        radius: config.radius,
+        angle: config.angle,
+        pitch: config.pitch,
+    };
 
     commands.spawn((
         Camera {
@@ -39,15 +50,77 @@ pub fn setup_lights_and_camera(mut commands: Commands) {
             high_pass_frequency: 0.6,
             ..default()
         },
-        Transform::from_xyz(
-            initial_radius * initial_angle.cos(),
-            7.5,
-            initial_radius * initial_angle.sin(),
-        )
-        .looking_at(Vec3::new(0.0, 5.0, 0.0), Vec3::Y),
+        Transform::from_translation(rig.eye()).looking_at(rig.focus, Vec3::Y),
         OrbitCamera {
-            radius: initial_radius,
-            angle: initial_angle,
+            mode: CameraMode::Orbit,
+            rig,
+            goal: rig,
         },
     ));
 }
+
+fn heatmap_image(grid: &DensityGrid) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: grid.width as u32,
+            height: grid.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+pub fn setup_heatmap(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    bounds: Res<RoomBounds>,
+    config: Res<HeatmapConfig>,
+) {
+    let heatmap = Heatmap::new(config.resolution, &bounds);
+    let height = bounds.ceiling - bounds.floor;
+
+    // The floor lies flat just above the floor; the side view stands at the
+    // back of the room facing the default camera.
+    let surfaces = [
+        (
+            HeatmapProjection::TopDown,
+            &heatmap.top,
+            meshes.add(Plane3d::new(Vec3::Y, Vec2::splat(bounds.radius))),
+            Transform::from_xyz(0.0, bounds.floor + 0.01, 0.0),
+        ),
+        (
+            HeatmapProjection::Side,
+            &heatmap.side,
+            meshes.add(Rectangle::new(bounds.radius * 2.0, height)),
+            Transform::from_xyz(0.0, bounds.floor + height / 2.0, -bounds.radius),
+        ),
+    ];
+
+    for (projection, grid, mesh, transform) in surfaces {
+        let image = images.add(heatmap_image(grid));
+        commands.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(image.clone()),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            })),
+            transform,
+            Visibility::Hidden,
+            Pickable::IGNORE,
+            HeatmapSurface { projection, image },
+        ));
+    }
+
+    commands.insert_resource(heatmap);
+}
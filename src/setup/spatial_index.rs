@@ -1,5 +1,5 @@
 use crate::components::Lantern;
-use crate::resources::{CELL_SIZE, SpatialIndex};
+use crate::resources::SpatialIndex;
 use bevy::prelude::*;
 
 pub fn setup_lantern_index(
@@ -7,10 +7,6 @@ pub fn setup_lantern_index(
     lantern_query: Query<(Entity, &Transform), With<Lantern>>,
 ) {
     for (entity, transform) in lantern_query.iter() {
-        let tile = (
-            (transform.translation.x / CELL_SIZE).floor() as i32,
-            (transform.translation.y / CELL_SIZE).floor() as i32,
-        );
-        spatial_index.map.entry(tile).or_default().insert(entity);
+        spatial_index.insert(entity, transform.translation.xz());
     }
 }
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::components::{
+    MothInspector, ReplayButton, ReplayLabel, ReplayPanel, ReplayScrubber, ReplayScrubberFill,
+    ShareButton, TimeControlButton, TimeControlLabel,
+};
+
+pub const BUTTON_COLOUR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+pub const BUTTON_HOVER_COLOUR: Color = Color::srgba(0.2, 0.2, 0.3, 0.8);
+
+pub fn setup_moth_inspector(mut commands: Commands) {
+    commands.spawn((
+        MothInspector,
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn setup_time_controls(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
+            column_gap: Val::Px(4.0),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            for (button, label) in [
+                (TimeControlButton::TogglePause, "Play/Pause"),
+                (TimeControlButton::Step, "Step"),
+                (TimeControlButton::Slower, "-"),
+                (TimeControlButton::Faster, "+"),
+            ] {
+                parent
+                    .spawn((
+                        button,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_COLOUR),
+                    ))
+                    .with_child((
+                        Text::new(label),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                    ));
+            }
+
+            parent.spawn((
+                TimeControlLabel,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                Node {
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_COLOUR),
+            ));
+        });
+}
+
+pub fn setup_share_button(mut commands: Commands) {
+    commands
+        .spawn((
+            ShareButton,
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                right: Val::Px(12.0),
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOUR),
+        ))
+        .with_child((
+            Text::new("Copy share link"),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+        ));
+}
+
+pub fn setup_replay_controls(mut commands: Commands) {
+    commands
+        .spawn((
+            ReplayPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                right: Val::Px(12.0),
+                column_gap: Val::Px(4.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            for (button, label) in [
+                (ReplayButton::Rewind, "|<"),
+                (ReplayButton::PreviousEvent, "<<"),
+                (ReplayButton::TogglePause, "Play/Pause"),
+                (ReplayButton::NextEvent, ">>"),
+            ] {
+                spawn_replay_button(parent, button, label);
+            }
+
+            parent
+                .spawn((
+                    ReplayScrubber,
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    Node {
+                        width: Val::Px(240.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(BUTTON_COLOUR),
+                ))
+                .with_child((
+                    ReplayScrubberFill,
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(1.0, 0.5, 0.0)),
+                ));
+
+            parent.spawn((
+                ReplayLabel,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                Node {
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_COLOUR),
+            ));
+
+            spawn_replay_button(parent, ReplayButton::Close, "Close");
+        });
+}
+
+fn spawn_replay_button(parent: &mut ChildSpawnerCommands, button: ReplayButton, label: &str) {
+    parent
+        .spawn((
+            button,
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOUR),
+        ))
+        .with_child((
+            Text::new(label),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+        ));
+}
@@ -0,0 +1,38 @@
+// Bevy's mesh vertex shader, with the wings flapped. The moth mesh only has
+// positions and normals, and is neither skinned nor morphed.
+
+#import bevy_pbr::{
+    forward_io::{Vertex, VertexOutput},
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+#import phototaxis::moth_wings::{flap, wing_rotation}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let position = flap(vertex.position, vertex.instance_index);
+    out.world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(position, 1.0)
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        wing_rotation(vertex.position, vertex.instance_index) * vertex.normal,
+        vertex.instance_index
+    );
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+
+#ifdef VISIBILITY_RANGE_DITHER
+    out.visibility_range_dither = mesh_functions::get_visibility_range_dither_level(
+        vertex.instance_index, world_from_local[3]);
+#endif
+
+    return out;
+}
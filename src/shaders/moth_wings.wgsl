@@ -0,0 +1,31 @@
+#define_import_path phototaxis::moth_wings
+
+#import bevy_pbr::mesh_functions
+
+// Half the width of the body, where the wings are hinged. Keep in step with
+// `WING_ROOT` in `wings.rs`.
+const WING_ROOT: f32 = 0.012;
+// How far the wings swing above and below flat, in radians.
+const FLAP_ANGLE: f32 = 1.0;
+
+// How a vertex is turned at this point in its moth's wingbeat: about the
+// wing's hinge for wing vertices, not at all for the body. The phase is
+// packed into the moth's `MeshTag`.
+fn wing_rotation(position: vec3<f32>, instance_index: u32) -> mat3x3<f32> {
+    if abs(position.x) <= WING_ROOT {
+        return mat3x3<f32>(vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0));
+    }
+
+    let phase = bitcast<f32>(mesh_functions::get_tag(instance_index));
+    // Mirrored for the left wing, so the tips rise and fall together
+    let angle = sign(position.x) * FLAP_ANGLE * sin(phase);
+    let c = cos(angle);
+    let s = sin(angle);
+    return mat3x3<f32>(vec3(c, s, 0.0), vec3(-s, c, 0.0), vec3(0.0, 0.0, 1.0));
+}
+
+// A vertex of the moth mesh, moved to where it is in the wingbeat.
+fn flap(position: vec3<f32>, instance_index: u32) -> vec3<f32> {
+    let hinge = vec3(sign(position.x) * WING_ROOT, 0.0, 0.0);
+    return hinge + wing_rotation(position, instance_index) * (position - hinge);
+}
@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+
+use crate::components::CameraRig;
+use crate::config::{CameraConfig, LanternConfig, LanternLayout, MAX_MOTHS, MothConfig};
+
+/// A setup carried in a link to the web build, as `key=value` pairs in the
+/// query string or hash, for example
+/// `?seed=7&moths=300&attraction=1.2&layout=ring:6:5&camera=0,5,0,18,1.57,0.14`.
+///
+/// Layouts are written `grid:<spacing>`, `ring:<count>:<radius>`,
+/// `line:<count>:<spacing>`, `random:<count>:<min spacing>`,
+/// `clusters:<clusters>:<per cluster>:<spread>` or
+/// `positions:<x>,<y>,<z>;<x>,<y>,<z>...`. The camera is its focus point
+/// followed by radius, angle and pitch.
+///
+/// Links come from anywhere, so values that would be expensive or break the
+/// scene are refused: more than `MAX_MOTHS` moths, layouts that fail
+/// `LanternLayout::is_valid`, numbers that aren't finite and cameras that
+/// aren't some distance from their focus.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShareSettings {
+    pub seed: Option<u64>,
+    pub moth_count: Option<i32>,
+    pub attraction_factor: Option<f32>,
+    pub layout: Option<LanternLayout>,
+    pub camera: Option<CameraRig>,
+}
+
+impl ShareSettings {
+    /// Reads whichever keys are present. Unknown keys and values that don't
+    /// parse are skipped with a warning.
+    pub fn from_query(query: &str) -> Self {
+        let mut settings = Self::default();
+        let query = query.trim_start_matches(['?', '#']);
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            let parsed = match key {
+                "seed" => value.parse().ok().map(|seed| settings.seed = Some(seed)),
+                "moths" => value
+                    .parse()
+                    .ok()
+                    .filter(|count| (0..=MAX_MOTHS).contains(count))
+                    .map(|count| settings.moth_count = Some(count)),
+                "attraction" => value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|factor| factor.is_finite())
+                    .map(|factor| settings.attraction_factor = Some(factor)),
+                "layout" => parse_layout(&value).map(|layout| settings.layout = Some(layout)),
+                "camera" => parse_camera(&value).map(|rig| settings.camera = Some(rig)),
+                _ => {
+                    warn!("Ignoring unknown link setting {key}");
+                    continue;
+                }
+            };
+            if parsed.is_none() {
+                warn!("Ignoring link setting {key}={value}");
+            }
+        }
+        settings
+    }
+
+    pub fn to_query(&self) -> String {
+        let mut pairs = Vec::new();
+        if let Some(seed) = self.seed {
+            pairs.push(format!("seed={seed}"));
+        }
+        if let Some(count) = self.moth_count {
+            pairs.push(format!("moths={count}"));
+        }
+        if let Some(factor) = self.attraction_factor {
+            pairs.push(format!("attraction={factor}"));
+        }
+        if let Some(layout) = &self.layout {
+            pairs.push(format!("layout={}", format_layout(layout)));
+        }
+        if let Some(rig) = &self.camera {
+            pairs.push(format!(
+                "camera={},{},{},{},{},{}",
+                rig.focus.x, rig.focus.y, rig.focus.z, rig.radius, rig.angle, rig.pitch
+            ));
+        }
+        pairs.join("&")
+    }
+
+    /// Overrides the configs with whatever the link set.
+    pub fn apply(
+        &self,
+        moth_config: &mut MothConfig,
+        lantern_config: &mut LanternConfig,
+        camera_config: &mut CameraConfig,
+    ) {
+        if let Some(count) = self.moth_count {
+            moth_config.moth_count = count;
+        }
+        if let Some(factor) = self.attraction_factor {
+            moth_config.attraction_factor = factor;
+        }
+        if let Some(layout) = &self.layout {
+            lantern_config.layout = layout.clone();
+        }
+        if let Some(rig) = self.camera {
+            camera_config.focus = rig.focus;
+            camera_config.radius = rig.radius;
+            camera_config.angle = rig.angle;
+            camera_config.pitch = rig.pitch;
+        }
+    }
+}
+
+fn numbers<T: std::str::FromStr>(text: &str, separator: char) -> Option<Vec<T>> {
+    text.split(separator)
+        .map(|number| number.trim().parse().ok())
+        .collect()
+}
+
+/// Reads a layout written the way share links write it, like `ring:6:5`.
+/// Layouts that fail `LanternLayout::is_valid` are refused.
+pub fn parse_layout(text: &str) -> Option<LanternLayout> {
+    parse_layout_parts(text).filter(LanternLayout::is_valid)
+}
+
+fn parse_layout_parts(text: &str) -> Option<LanternLayout> {
+    let (kind, rest) = text.split_once(':')?;
+    if kind == "positions" {
+        let positions = rest
+            .split(';')
+            .map(|position| match numbers::<f32>(position, ',')?.as_slice() {
+                &[x, y, z] => Some(Vec3::new(x, y, z).into()),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        return Some(LanternLayout::Positions(positions));
+    }
+
+    let values = numbers::<f32>(rest, ':')?;
+    let count = |value: f32| (value >= 0.0 && value.fract() == 0.0).then_some(value as usize);
+    match (kind, values.as_slice()) {
+        ("grid", &[spacing]) => Some(LanternLayout::Grid { spacing }),
+        ("ring", &[n, radius]) => Some(LanternLayout::Ring {
+            count: count(n)?,
+            radius,
+        }),
+        ("line", &[n, spacing]) => Some(LanternLayout::Line {
+            count: count(n)?,
+            spacing,
+        }),
+        ("random", &[n, min_spacing]) => Some(LanternLayout::Random {
+            count: count(n)?,
+            min_spacing,
+        }),
+        ("clusters", &[clusters, per_cluster, spread]) => Some(LanternLayout::Clusters {
+            clusters: count(clusters)?,
+            per_cluster: count(per_cluster)?,
+            spread,
+        }),
+        _ => None,
+    }
+}
+
+fn format_layout(layout: &LanternLayout) -> String {
+    match layout {
+        LanternLayout::Grid { spacing } => format!("grid:{spacing}"),
+        LanternLayout::Ring { count, radius } => format!("ring:{count}:{radius}"),
+        LanternLayout::Line { count, spacing } => format!("line:{count}:{spacing}"),
+        LanternLayout::Random { count, min_spacing } => format!("random:{count}:{min_spacing}"),
+        LanternLayout::Clusters {
+            clusters,
+            per_cluster,
+            spread,
+        } => format!("clusters:{clusters}:{per_cluster}:{spread}"),
+        LanternLayout::Positions(positions) => {
+            let positions: Vec<String> = positions
+                .iter()
+                .map(|placement| {
+                    let p = placement.position;
+                    format!("{},{},{}", p.x, p.y, p.z)
+                })
+                .collect();
+            format!("positions:{}", positions.join(";"))
+        }
+    }
+}
+
+fn parse_camera(text: &str) -> Option<CameraRig> {
+    let values = numbers::<f32>(text, ',')?;
+    if !values.iter().all(|value| value.is_finite()) {
+        return None;
+    }
+    match values.as_slice() {
+        &[x, y, z, radius, angle, pitch] if radius > 0.0 => Some(CameraRig {
+            focus: Vec3::new(x, y, z),
+            radius,
+            angle,
+            pitch,
+        }),
+        _ => None,
+    }
+}
+
+/// Decodes `%XX` escapes, which some browsers and chat apps add to links.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rand::prelude::{Entropy, EntropyPlugin, WyRand};
+
+use crate::components::{
+    Ceiling, Lantern, LanternBob, LanternId, LightTrap, Moth, MothState, MothStats, Perch,
+    Steering, Velocity,
+};
+use crate::config::{LanternConfig, MothConfig, RecordingConfig, TrapConfig};
+use crate::events::{LanternFlickered, LanternTurnedOff, LanternTurnedOn, MothCaptured};
+use crate::resources::{
+    NextLanternId, Recorder, ReplayPlayer, RoomBounds, SnapshotInfo, SpatialIndex,
+};
+use crate::setup::{setup_ceiling, setup_lantern_index, setup_lanterns, setup_moths, setup_traps};
+use crate::systems::{
+    enforce_boundary_system, lantern_bob_system, lantern_event_log_system, lantern_index_system,
+    lantern_power_system, moth_attraction_system, moth_collision_system, moth_movement_system,
+    moth_perch_system, moth_wander_system, record_events_system, record_frame_system, replaying,
+    simulation_advancing, trap_capture_system, trap_sample_system,
+};
+use crate::wings::MothMaterial;
+
+/// The fixed frame length headless apps advance by on every update.
+pub const HEADLESS_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// The moth steering and movement systems. Anything reading where moths ended
+/// up this frame should run after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MothUpdate;
+
+/// Moths, lanterns and the systems that move them, plus run recording,
+/// without rendering or input. Expects an `EntropyPlugin::<WyRand>` to be
+/// added alongside it.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex>()
+            .init_resource::<RoomBounds>()
+            .init_resource::<MothConfig>()
+            .init_resource::<LanternConfig>()
+            .init_resource::<NextLanternId>()
+            .init_resource::<TrapConfig>()
+            .init_resource::<SnapshotInfo>()
+            .init_resource::<RecordingConfig>()
+            .init_resource::<Recorder>()
+            .init_resource::<ReplayPlayer>()
+            // Snapshots need it, and headless apps don't add `TransformPlugin`
+            .register_type::<Transform>()
+            .register_type::<Moth>()
+            .register_type::<Velocity>()
+            .register_type::<MothState>()
+            .register_type::<MothStats>()
+            .register_type::<Perch>()
+            .register_type::<Steering>()
+            .register_type::<Lantern>()
+            .register_type::<LanternId>()
+            .register_type::<LanternBob>()
+            .register_type::<LightTrap>()
+            .register_type::<Ceiling>()
+            .register_type::<Entropy<WyRand>>()
+            .register_type::<MothConfig>()
+            .register_type::<LanternConfig>()
+            .register_type::<RoomBounds>()
+            .register_type::<NextLanternId>()
+            .register_type::<SnapshotInfo>()
+            .add_event::<LanternTurnedOn>()
+            .add_event::<LanternTurnedOff>()
+            .add_event::<LanternFlickered>()
+            .add_event::<MothCaptured>()
+            .add_systems(
+                Startup,
+                (
+                    setup_ceiling,
+                    setup_lanterns,
+                    setup_traps,
+                    setup_moths,
+                    setup_lantern_index,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        lantern_bob_system.run_if(not(replaying)),
+                        lantern_index_system,
+                    )
+                        .chain(),
+                    // Collision runs last so no moth ends a frame inside a lantern
+                    (
+                        moth_perch_system,
+                        moth_wander_system,
+                        moth_attraction_system,
+                        moth_movement_system,
+                        enforce_boundary_system,
+                        moth_collision_system,
+                    )
+                        .chain()
+                        .in_set(MothUpdate)
+                        .after(lantern_index_system)
+                        .run_if(simulation_advancing)
+                        .run_if(not(replaying)),
+                    (lantern_power_system, lantern_event_log_system)
+                        .chain()
+                        .run_if(simulation_advancing)
+                        .run_if(not(replaying)),
+                    (trap_capture_system, trap_sample_system)
+                        .chain()
+                        .after(MothUpdate)
+                        .run_if(simulation_advancing)
+                        .run_if(not(replaying)),
+                    (record_events_system, record_frame_system)
+                        .chain()
+                        .after(MothUpdate)
+                        .after(lantern_power_system)
+                        .after(trap_sample_system)
+                        .run_if(simulation_advancing),
+                ),
+            );
+    }
+}
+
+/// A windowless app running the simulation from `seed`, advancing by
+/// `HEADLESS_TIMESTEP` on every `update` regardless of wall-clock time.
+pub fn headless_app(seed: u64) -> App {
+    headless_app_with_timestep(seed, HEADLESS_TIMESTEP)
+}
+
+/// Like `headless_app`, advancing by `timestep` on every `update`.
+pub fn headless_app_with_timestep(seed: u64, timestep: Duration) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        EntropyPlugin::<WyRand>::with_seed(seed.to_le_bytes()),
+        SimulationPlugin,
+    ))
+    .init_resource::<Assets<Mesh>>()
+    .init_resource::<Assets<StandardMaterial>>()
+    .init_resource::<Assets<MothMaterial>>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    // Virtual time would otherwise clamp long timesteps to 250 ms
+    app.world_mut()
+        .resource_mut::<Time<Virtual>>()
+        .set_max_delta(timestep);
+    app
+}
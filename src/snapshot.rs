@@ -0,0 +1,185 @@
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::*;
+use bevy::render::mesh::MeshTag;
+use bevy::scene::serde::SceneDeserializer;
+use bevy::scene::{DynamicEntity, ron};
+use bevy_rand::prelude::{Entropy, Global, WyRand};
+use serde::de::DeserializeSeed;
+
+use crate::components::{
+    Ceiling, Fixture, Lantern, LanternBob, LanternId, LightTrap, Moth, MothPerception, MothState,
+    MothStats, Perch, Steering, Trail, Velocity,
+};
+use crate::config::{LanternConfig, MothConfig};
+use crate::resources::{
+    LanternInteraction, MothAssets, MothSelection, NextLanternId, ReplayPlayer, RoomBounds,
+    SnapshotInfo, SpatialIndex,
+};
+use crate::setup::{insert_lantern_visuals, insert_trap_visuals};
+
+/// Serialises moths, lanterns and their traps, the ceiling, every random
+/// number generator and the simulation configs to a RON scene. Meshes,
+/// materials and lights are left out and rebuilt on restore, as are trails and
+/// perception, which the systems refill on their own.
+pub fn save_snapshot(world: &mut World) -> Result<String> {
+    let elapsed_secs = world.resource::<Time>().elapsed_secs();
+    world.insert_resource(SnapshotInfo { elapsed_secs });
+
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(
+            With<Moth>,
+            With<Lantern>,
+            With<Ceiling>,
+            With<Entropy<WyRand>>,
+        )>>()
+        .iter(world)
+        .collect();
+
+    let scene = DynamicSceneBuilder::from_world(world)
+        .deny_all()
+        .allow_component::<Transform>()
+        .allow_component::<Moth>()
+        .allow_component::<Velocity>()
+        .allow_component::<MothState>()
+        .allow_component::<MothStats>()
+        .allow_component::<Perch>()
+        .allow_component::<Steering>()
+        .allow_component::<Lantern>()
+        .allow_component::<LanternId>()
+        .allow_component::<LanternBob>()
+        .allow_component::<LightTrap>()
+        .allow_component::<Ceiling>()
+        .allow_component::<Entropy<WyRand>>()
+        .allow_resource::<MothConfig>()
+        .allow_resource::<LanternConfig>()
+        .allow_resource::<RoomBounds>()
+        .allow_resource::<NextLanternId>()
+        .allow_resource::<SnapshotInfo>()
+        .extract_entities(entities.into_iter())
+        .extract_resources()
+        .build();
+
+    let registry = world.resource::<AppTypeRegistry>().read();
+    Ok(scene.serialize(&registry)?)
+}
+
+fn has_component<T: Reflect + TypePath>(entity: &DynamicEntity) -> bool {
+    entity
+        .components
+        .iter()
+        .any(|component| component.represents::<T>())
+}
+
+/// Replaces the current simulation with one saved by `save_snapshot`.
+pub fn restore_snapshot(world: &mut World, text: &str) -> Result {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = {
+        let registry = registry.read();
+        let mut deserializer = ron::de::Deserializer::from_str(text)?;
+        SceneDeserializer {
+            type_registry: &registry,
+        }
+        .deserialize(&mut deserializer)?
+    };
+
+    let existing: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Moth>, With<Lantern>, With<Ceiling>)>>()
+        .iter(world)
+        .collect();
+    for entity in existing {
+        world.despawn(entity);
+    }
+
+    // The only generator that isn't on a moth or a lantern is the global one.
+    // It's written over the live global generator rather than spawned as a
+    // second one.
+    let mut entity_map = EntityHashMap::default();
+    let live_global = world
+        .query_filtered::<Entity, (With<Entropy<WyRand>>, With<Global>)>()
+        .iter(world)
+        .next();
+    let saved_global = scene.entities.iter().find(|entity| {
+        has_component::<Entropy<WyRand>>(entity)
+            && !has_component::<Moth>(entity)
+            && !has_component::<Lantern>(entity)
+    });
+    if let (Some(live), Some(saved)) = (live_global, saved_global) {
+        entity_map.insert(saved.entity, live);
+    }
+
+    scene.write_to_world_with(world, &mut entity_map, &registry)?;
+
+    rebuild_visuals(world);
+    resume_bobbing(world);
+
+    let lanterns: Vec<(Entity, Vec2)> = world
+        .query_filtered::<(Entity, &Transform), With<Lantern>>()
+        .iter(world)
+        .map(|(entity, transform)| (entity, transform.translation.xz()))
+        .collect();
+    let mut spatial_index = world.resource_mut::<SpatialIndex>();
+    spatial_index.clear();
+    for (entity, pos) in lanterns {
+        spatial_index.insert(entity, pos);
+    }
+
+    if let Some(mut selection) = world.get_resource_mut::<MothSelection>() {
+        selection.selected = None;
+    }
+    if let Some(mut interaction) = world.get_resource_mut::<LanternInteraction>() {
+        interaction.selected = None;
+        interaction.dragging.clear();
+    }
+    // The replay's stand-ins were despawned with everything else
+    world.insert_resource(ReplayPlayer::default());
+
+    Ok(())
+}
+
+fn rebuild_visuals(world: &mut World) {
+    world.init_resource::<MothAssets>();
+    let assets = world.resource::<MothAssets>().clone();
+    let moths: Vec<Entity> = world
+        .query_filtered::<Entity, (With<Moth>, Without<Mesh3d>)>()
+        .iter(world)
+        .collect();
+    for moth in moths {
+        world.entity_mut(moth).insert((
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(assets.material.clone()),
+            MeshTag::default(),
+            MothPerception::default(),
+            Trail::default(),
+        ));
+    }
+
+    let lanterns: Vec<(Entity, Fixture, Option<LightTrap>)> = world
+        .query_filtered::<(Entity, &Lantern, Option<&LightTrap>), Without<Mesh3d>>()
+        .iter(world)
+        .map(|(entity, lantern, trap)| (entity, lantern.fixture, trap.cloned()))
+        .collect();
+    world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+        world.resource_scope(|world, mut materials: Mut<Assets<StandardMaterial>>| {
+            let mut commands = world.commands();
+            for (entity, fixture, trap) in &lanterns {
+                let mut lantern = commands.entity(*entity);
+                insert_lantern_visuals(&mut lantern, &mut meshes, &mut materials, *fixture);
+                if let Some(trap) = trap {
+                    insert_trap_visuals(&mut lantern, &mut meshes, &mut materials, trap, *fixture);
+                }
+            }
+        });
+    });
+    world.flush();
+}
+
+/// Bobbing follows elapsed time, which keeps running across a restore, so
+/// each lantern's phase is shifted to pick up where the snapshot left off.
+fn resume_bobbing(world: &mut World) {
+    let saved = world.resource::<SnapshotInfo>().elapsed_secs;
+    let now = world.resource::<Time>().elapsed_secs();
+    let shift = (saved - now) * world.resource::<LanternConfig>().bob_speed;
+    for mut bob in world.query::<&mut LanternBob>().iter_mut(world) {
+        bob.phase_offset += shift;
+    }
+}
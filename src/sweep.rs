@@ -0,0 +1,231 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use bevy::prelude::*;
+use bevy::scene::ron;
+use serde::Deserialize;
+
+use crate::components::{Moth, MothState, MothStats};
+use crate::config::{LanternConfig, LanternLayout, MothConfig};
+use crate::events::LanternTurnedOn;
+use crate::simulation::{HEADLESS_TIMESTEP, headless_app};
+
+/// Parameter values to try. Every combination of the lists is run once.
+/// Fields left out of a definition file take their usual defaults.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Sweep {
+    pub attraction_factor: Vec<f32>,
+    pub view_radius: Vec<f32>,
+    pub on_chance: Vec<f64>,
+    pub seeds: Vec<u64>,
+    /// Simulated seconds each run lasts.
+    pub duration_secs: f32,
+    pub moth_count: i32,
+    pub layout: LanternLayout,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        let moth = MothConfig::default();
+        let lantern = LanternConfig::default();
+        Self {
+            attraction_factor: vec![moth.attraction_factor],
+            view_radius: vec![moth.view_radius],
+            on_chance: vec![lantern.on_chance],
+            seeds: vec![0],
+            duration_secs: 60.0,
+            moth_count: moth.moth_count,
+            layout: lantern.layout,
+        }
+    }
+}
+
+/// One combination of swept parameters.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RunParameters {
+    pub seed: u64,
+    pub attraction_factor: f32,
+    pub view_radius: f32,
+    pub on_chance: f64,
+}
+
+/// What a run produced, averaged over its moths.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RunSummary {
+    pub parameters: RunParameters,
+    /// Fraction of moths attracted to a light, averaged over every tick.
+    pub mean_attracted: f32,
+    /// Fraction of moths attracted to a light on the last tick.
+    pub final_attracted: f32,
+    /// Seconds each moth spent within view of a lit lantern.
+    pub mean_time_near_lights: f32,
+    /// How many times any lantern turned on.
+    pub turn_ons: usize,
+}
+
+impl Sweep {
+    pub fn from_ron(text: &str) -> Result<Self> {
+        Ok(ron::de::from_str(text)?)
+    }
+
+    /// Every combination, seeds varying fastest.
+    pub fn runs(&self) -> Vec<RunParameters> {
+        let mut runs = Vec::new();
+        for &attraction_factor in &self.attraction_factor {
+            for &view_radius in &self.view_radius {
+                for &on_chance in &self.on_chance {
+                    for &seed in &self.seeds {
+                        runs.push(RunParameters {
+                            seed,
+                            attraction_factor,
+                            view_radius,
+                            on_chance,
+                        });
+                    }
+                }
+            }
+        }
+        runs
+    }
+
+    /// Runs one combination headlessly for `duration_secs` of simulated time.
+    pub fn run(&self, parameters: RunParameters) -> RunSummary {
+        let mut app = headless_app(parameters.seed);
+        app.insert_resource(MothConfig {
+            moth_count: self.moth_count,
+            view_radius: parameters.view_radius,
+            attraction_factor: parameters.attraction_factor,
+            ..default()
+        })
+        .insert_resource(LanternConfig {
+            layout: self.layout.clone(),
+            on_chance: parameters.on_chance,
+            ..default()
+        });
+
+        // The first update only starts the clock
+        app.update();
+
+        let ticks = (self.duration_secs / HEADLESS_TIMESTEP.as_secs_f32()).round() as usize;
+        let mut attracted_sum = 0.0;
+        let mut attracted = 0.0;
+        let mut turn_ons = 0;
+        for _ in 0..ticks {
+            app.update();
+            let world = app.world_mut();
+            attracted = attracted_fraction(world);
+            attracted_sum += attracted;
+            turn_ons += world
+                .resource::<Events<LanternTurnedOn>>()
+                .iter_current_update_events()
+                .count();
+        }
+
+        let world = app.world_mut();
+        let times: Vec<f32> = world
+            .query_filtered::<&MothStats, With<Moth>>()
+            .iter(world)
+            .map(|stats| stats.time_near_lights)
+            .collect();
+
+        RunSummary {
+            parameters,
+            mean_attracted: if ticks > 0 {
+                attracted_sum / ticks as f32
+            } else {
+                0.0
+            },
+            final_attracted: attracted,
+            mean_time_near_lights: mean(&times),
+            turn_ons,
+        }
+    }
+
+    /// Runs every combination, spread over `threads` threads, and returns the
+    /// summaries in the order of `runs`. `on_finished` is called as each run
+    /// completes, from whichever thread ran it.
+    pub fn run_all(
+        &self,
+        threads: usize,
+        on_finished: impl Fn(&RunSummary) + Sync,
+    ) -> Vec<RunSummary> {
+        let runs = self.runs();
+        let next = AtomicUsize::new(0);
+
+        let mut finished: Vec<(usize, RunSummary)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.clamp(1, runs.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&parameters) = runs.get(index) else {
+                                break finished;
+                            };
+                            let summary = self.run(parameters);
+                            on_finished(&summary);
+                            finished.push((index, summary));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        finished.sort_unstable_by_key(|(index, _)| *index);
+        finished.into_iter().map(|(_, summary)| summary).collect()
+    }
+}
+
+fn attracted_fraction(world: &mut World) -> f32 {
+    let mut moths = 0;
+    let mut attracted = 0;
+    for state in world.query_filtered::<&MothState, With<Moth>>().iter(world) {
+        moths += 1;
+        if *state == MothState::Attracted {
+            attracted += 1;
+        }
+    }
+    if moths == 0 {
+        0.0
+    } else {
+        attracted as f32 / moths as f32
+    }
+}
+
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+/// Writes one row per run, with a header.
+pub fn write_csv(summaries: &[RunSummary], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "seed,attraction_factor,view_radius,on_chance,mean_attracted,final_attracted,mean_time_near_lights,turn_ons"
+    )?;
+    for summary in summaries {
+        let parameters = summary.parameters;
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            parameters.seed,
+            parameters.attraction_factor,
+            parameters.view_radius,
+            parameters.on_chance,
+            summary.mean_attracted,
+            summary.final_attracted,
+            summary.mean_time_near_lights,
+            summary.turn_ons,
+        )?;
+    }
+    writer.flush()
+}
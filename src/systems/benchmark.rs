@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+
+use crate::components::Moth;
+use crate::config::BenchmarkConfig;
+use crate::resources::FrameTimes;
+
+pub fn frame_time_report_system(
+    mut frame_times: ResMut<FrameTimes>,
+    config: Res<BenchmarkConfig>,
+    time: Res<Time<Real>>,
+    moth_query: Query<(), With<Moth>>,
+) {
+    let delta = time.delta_secs();
+    if delta == 0.0 {
+        return;
+    }
+    frame_times.samples.push(delta * 1000.0);
+    frame_times.since_report += delta;
+    if frame_times.since_report < config.report_interval {
+        return;
+    }
+
+    let mut samples = std::mem::take(&mut frame_times.samples);
+    frame_times.since_report = 0.0;
+    samples.sort_by(f32::total_cmp);
+
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let p99 = samples[((samples.len() - 1) as f32 * 0.99) as usize];
+    let worst = samples[samples.len() - 1];
+    info!(
+        "{} moths: mean {mean:.2} ms ({:.0} fps), p99 {p99:.2} ms, worst {worst:.2} ms",
+        moth_query.iter().count(),
+        1000.0 / mean,
+    );
+}
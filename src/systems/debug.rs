@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::components::{Lantern, Moth, Steering};
+use crate::config::{LanternConfig, MothConfig};
+use crate::resources::{CELL_SIZE, DebugOverlay, RoomBounds, SpatialIndex};
+
+/// Steering vectors are small next to the room, so arrows are stretched to
+/// stay readable and capped so strong pulls don't cross the scene.
+const ARROW_SCALE: f32 = 2.0;
+const MAX_ARROW_LENGTH: f32 = 3.0;
+
+pub fn debug_overlay_toggle_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if keys.just_pressed(KeyCode::KeyG) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn debug_overlay_system(
+    mut gizmos: Gizmos,
+    overlay: Res<DebugOverlay>,
+    spatial_index: Res<SpatialIndex>,
+    bounds: Res<RoomBounds>,
+    moth_config: Res<MothConfig>,
+    lantern_config: Res<LanternConfig>,
+    lantern_query: Query<&Transform, With<Lantern>>,
+    moth_query: Query<(&Transform, &Steering), With<Moth>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    // Index cells tile the floor plane, so each one is a column spanning the
+    // room's height
+    for (tile, _) in spatial_index.cells() {
+        let centre = Vec3::new(
+            (tile.0 as f32 + 0.5) * CELL_SIZE,
+            (bounds.floor + bounds.ceiling) / 2.0,
+            (tile.1 as f32 + 0.5) * CELL_SIZE,
+        );
+        gizmos.cuboid(
+            Transform::from_translation(centre).with_scale(Vec3::new(
+                CELL_SIZE,
+                bounds.ceiling - bounds.floor,
+                CELL_SIZE,
+            )),
+            Color::srgba(0.3, 1.0, 0.3, 0.4),
+        );
+    }
+
+    let room_colour = Color::srgba(0.6, 0.6, 1.0, 0.5);
+    for y in [bounds.floor, bounds.ceiling] {
+        gizmos.circle(
+            Isometry3d::new(
+                Vec3::new(0.0, y, 0.0),
+                Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            ),
+            bounds.radius,
+            room_colour,
+        );
+    }
+    for i in 0..8 {
+        let edge = Vec2::from_angle(i as f32 / 8.0 * std::f32::consts::TAU) * bounds.radius;
+        gizmos.line(
+            Vec3::new(edge.x, bounds.floor, edge.y),
+            Vec3::new(edge.x, bounds.ceiling, edge.y),
+            room_colour,
+        );
+    }
+
+    for transform in lantern_query.iter() {
+        let isometry = Isometry3d::from_translation(transform.translation);
+        gizmos.sphere(
+            isometry,
+            moth_config.view_radius,
+            Color::srgba(1.0, 0.8, 0.2, 0.15),
+        );
+        gizmos.sphere(
+            isometry,
+            lantern_config.physical_radius,
+            Color::srgb(1.0, 0.3, 0.2),
+        );
+    }
+
+    for (transform, steering) in moth_query.iter() {
+        let start = transform.translation;
+        for (force, colour) in [
+            (steering.attraction, Color::srgb(1.0, 0.6, 0.0)),
+            (steering.wander, Color::srgb(0.3, 0.7, 1.0)),
+        ] {
+            if force != Vec3::ZERO {
+                let end = start + (force * ARROW_SCALE).clamp_length_max(MAX_ARROW_LENGTH);
+                gizmos.arrow(start, end, colour);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Writes `text` to `path`. In the browser it's offered as a download named
+/// after the file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_text_file(path: &Path, text: &str) {
+    match std::fs::write(path, text) {
+        Ok(()) => info!("Saved {}", path.display()),
+        Err(err) => warn!("Failed to write {}: {err}", path.display()),
+    }
+}
+
+/// Reads `path` into `load`. In the browser a file picker opens instead, and
+/// the chosen file arrives in `load` once it has been read.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn request_text_file(path: &Path, load: &Arc<Mutex<Option<String>>>) {
+    match std::fs::read_to_string(path) {
+        Ok(text) => *load.lock().unwrap() = Some(text),
+        Err(err) => warn!("Failed to read {}: {err}", path.display()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_text_file(path: &Path, text: &str) {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "download.ron".to_owned());
+    if let Err(err) = web::download(text, &filename) {
+        warn!("Failed to download {filename}: {err:?}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn request_text_file(path: &Path, load: &Arc<Mutex<Option<String>>>) {
+    let accept = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    if let Err(err) = web::upload(&accept, load.clone()) {
+        warn!("Failed to open file picker: {err:?}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::sync::{Arc, Mutex};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen::prelude::*;
+
+    fn document() -> Result<web_sys::Document, JsValue> {
+        web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| JsValue::from_str("no document"))
+    }
+
+    pub fn download(text: &str, filename: &str) -> Result<(), JsValue> {
+        let parts = js_sys::Array::of1(&JsValue::from_str(text));
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("application/octet-stream");
+        let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let anchor: web_sys::HtmlAnchorElement = document()?.create_element("a")?.dyn_into()?;
+        anchor.set_href(&url);
+        anchor.set_download(filename);
+        anchor.click();
+        web_sys::Url::revoke_object_url(&url)
+    }
+
+    pub fn upload(accept: &str, load: Arc<Mutex<Option<String>>>) -> Result<(), JsValue> {
+        let input: web_sys::HtmlInputElement = document()?.create_element("input")?.dyn_into()?;
+        input.set_type("file");
+        input.set_accept(accept);
+
+        let picker = input.clone();
+        let on_change = Closure::<dyn FnMut()>::new(move || {
+            let Some(file) = picker.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let Ok(reader) = web_sys::FileReader::new() else {
+                return;
+            };
+
+            let load = load.clone();
+            let finished = reader.clone();
+            let on_load = Closure::<dyn FnMut()>::new(move || {
+                if let Some(text) = finished.result().ok().and_then(|result| result.as_string()) {
+                    *load.lock().unwrap() = Some(text);
+                }
+            });
+            reader.set_onload(Some(on_load.as_ref().unchecked_ref()));
+            on_load.forget();
+            let _ = reader.read_as_text(&file);
+        });
+        input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        on_change.forget();
+
+        input.click();
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::components::{HeatmapProjection, HeatmapSurface, Moth};
+use crate::config::HeatmapConfig;
+use crate::resources::{DensityGrid, Heatmap, RoomBounds};
+
+const HEAT_STOPS: [Vec3; 4] = [
+    Vec3::new(0.0, 0.0, 0.0),
+    Vec3::new(0.5, 0.0, 0.6),
+    Vec3::new(1.0, 0.5, 0.0),
+    Vec3::new(1.0, 1.0, 0.6),
+];
+
+fn heat_colour(t: f32) -> [u8; 4] {
+    let scaled = t.clamp(0.0, 1.0) * (HEAT_STOPS.len() - 1) as f32;
+    let index = (scaled as usize).min(HEAT_STOPS.len() - 2);
+    let rgb = HEAT_STOPS[index].lerp(HEAT_STOPS[index + 1], scaled - index as f32);
+    [
+        (rgb.x * 255.0) as u8,
+        (rgb.y * 255.0) as u8,
+        (rgb.z * 255.0) as u8,
+        (t.clamp(0.0, 1.0) * 255.0) as u8,
+    ]
+}
+
+/// Renders a grid as RGBA8 pixels. Values are normalised to the busiest cell
+/// and square-rooted so sparse areas still show up next to hotspots.
+pub fn grid_to_rgba(grid: &DensityGrid) -> Vec<u8> {
+    let max = grid.max();
+    grid.cells
+        .iter()
+        .flat_map(|&value| {
+            let t = if max > 0.0 { (value / max).sqrt() } else { 0.0 };
+            heat_colour(t)
+        })
+        .collect()
+}
+
+pub fn grid_to_csv(grid: &DensityGrid) -> String {
+    grid.cells
+        .chunks(grid.width)
+        .map(|row| {
+            row.iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn heatmap_accumulate_system(
+    moth_query: Query<&Transform, With<Moth>>,
+    mut heatmap: ResMut<Heatmap>,
+    bounds: Res<RoomBounds>,
+    time: Res<Time>,
+) {
+    let weight = time.delta_secs();
+    for transform in moth_query.iter() {
+        heatmap.record(transform.translation, &bounds, weight);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_heatmap(heatmap: &Heatmap, config: &HeatmapConfig) {
+    for (name, grid) in [("top", &heatmap.top), ("side", &heatmap.side)] {
+        let png_path = config.export_dir.join(format!("heatmap-{name}.png"));
+        let csv_path = config.export_dir.join(format!("heatmap-{name}.csv"));
+
+        if let Err(err) = image::save_buffer(
+            &png_path,
+            &grid_to_rgba(grid),
+            grid.width as u32,
+            grid.height as u32,
+            image::ColorType::Rgba8,
+        ) {
+            warn!("Failed to write {}: {err}", png_path.display());
+        }
+        if let Err(err) = std::fs::write(&csv_path, grid_to_csv(grid)) {
+            warn!("Failed to write {}: {err}", csv_path.display());
+        }
+    }
+    info!("Exported heatmap to {}", config.export_dir.display());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn export_heatmap(_heatmap: &Heatmap, _config: &HeatmapConfig) {
+    warn!("Heatmap export is not available in the browser");
+}
+
+/// `H` shows or hides the heatmap, `Shift+H` exports it and `Ctrl+H` clears it.
+pub fn heatmap_keys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<HeatmapConfig>,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    if !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        export_heatmap(&heatmap, &config);
+    } else if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        heatmap.top.clear();
+        heatmap.side.clear();
+    } else {
+        config.visible = !config.visible;
+    }
+}
+
+pub fn heatmap_render_system(
+    mut surface_query: Query<(
+        &HeatmapSurface,
+        &MeshMaterial3d<StandardMaterial>,
+        &mut Visibility,
+    )>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    heatmap: Res<Heatmap>,
+    config: Res<HeatmapConfig>,
+    time: Res<Time<Real>>,
+    mut since_refresh: Local<f32>,
+) {
+    for (_, _, mut visibility) in surface_query.iter_mut() {
+        visibility.set_if_neq(if config.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+
+    if !config.visible {
+        return;
+    }
+
+    *since_refresh += time.delta_secs();
+    if *since_refresh < config.refresh_interval && !config.is_changed() {
+        return;
+    }
+    *since_refresh = 0.0;
+
+    for (surface, material, _) in surface_query.iter() {
+        let grid = match surface.projection {
+            HeatmapProjection::TopDown => &heatmap.top,
+            HeatmapProjection::Side => &heatmap.side,
+        };
+        if let Some(image) = images.get_mut(&surface.image) {
+            image.data = Some(grid_to_rgba(grid));
+        }
+        // Touch the material so it picks up the new texture data
+        materials.get_mut(&material.0);
+    }
+}
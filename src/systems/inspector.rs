@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::components::{
+    Lantern, Moth, MothInspector, MothPerception, MothState, MothStats, OrbitCamera, Velocity,
+};
+use crate::resources::MothSelection;
+
+pub fn follow_selected_moth_system(
+    selection: Res<MothSelection>,
+    moth_query: Query<&Transform, With<Moth>>,
+    mut camera_query: Query<&mut OrbitCamera>,
+) {
+    if let Some(moth_transform) = selection
+        .selected
+        .and_then(|entity| moth_query.get(entity).ok())
+        && let Ok(mut orbit_camera) = camera_query.single_mut()
+    {
+        orbit_camera.goal.focus = moth_transform.translation;
+    }
+}
+
+pub fn moth_inspector_system(
+    selection: Res<MothSelection>,
+    moth_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &MothState,
+            &MothPerception,
+            &MothStats,
+        ),
+        With<Moth>,
+    >,
+    lantern_query: Query<&Transform, With<Lantern>>,
+    mut inspector_query: Query<(&mut Text, &mut Visibility), With<MothInspector>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((mut text, mut visibility)) = inspector_query.single_mut() else {
+        return;
+    };
+
+    let Some((entity, (transform, velocity, state, perception, stats))) = selection
+        .selected
+        .and_then(|entity| moth_query.get(entity).ok().map(|moth| (entity, moth)))
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let position = transform.translation;
+    let strongest_light = match perception
+        .strongest_light
+        .and_then(|light| lantern_query.get(light).ok().map(|t| (light, t)))
+    {
+        Some((light, light_transform)) => format!(
+            "{light} at {:.1}",
+            light_transform.translation.distance(position)
+        ),
+        None => "none".to_string(),
+    };
+
+    *visibility = Visibility::Inherited;
+    text.0 = format!(
+        "Moth {entity}\n\
+         Position: ({:.1}, {:.1}, {:.1})\n\
+         Speed: {:.2}\n\
+         State: {state:?}\n\
+         Strongest light: {strongest_light}\n\
+         Time near lights: {:.1}s",
+        position.x,
+        position.y,
+        position.z,
+        velocity.0.length(),
+        stats.time_near_lights,
+    );
+
+    gizmos.sphere(
+        Isometry3d::from_translation(position),
+        0.25,
+        Color::srgb(0.4, 0.8, 1.0),
+    );
+}
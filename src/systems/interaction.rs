@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use rand::Rng;
+
+use crate::components::{Ceiling, Lantern, LanternBob, Moth};
+use crate::config::LanternConfig;
+use crate::events::{LanternTurnedOff, LanternTurnedOn};
+use crate::resources::{
+    LanternInteraction, MothSelection, NextLanternId, RoomBounds, SpatialIndex,
+};
+use crate::setup::spawn_lantern;
+use crate::systems::lantern::{SwitchableLantern, light_intensity, switch_off, switch_on};
+
+fn despawn_lantern(
+    commands: &mut Commands,
+    entity: Entity,
+    interaction: &mut LanternInteraction,
+    spatial_index: &mut SpatialIndex,
+) {
+    commands.entity(entity).try_despawn();
+    spatial_index.remove(entity);
+    interaction.dragging.remove(&entity);
+    if interaction.selected == Some(entity) {
+        interaction.selected = None;
+    }
+}
+
+/// Primary click selects and toggles a lantern, secondary click deletes it.
+#[allow(clippy::too_many_arguments)]
+pub fn lantern_click_system(
+    mut commands: Commands,
+    mut click_events: EventReader<Pointer<Click>>,
+    mut lantern_query: Query<SwitchableLantern>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut interaction: ResMut<LanternInteraction>,
+    mut spatial_index: ResMut<SpatialIndex>,
+    config: Res<LanternConfig>,
+    mut turned_on: EventWriter<LanternTurnedOn>,
+    mut turned_off: EventWriter<LanternTurnedOff>,
+) {
+    for click in click_events.read() {
+        let entity = click.target;
+
+        // A drag ends with a click on whatever it started on, which is not a toggle
+        if interaction.dragging.contains(&entity) {
+            continue;
+        }
+
+        let Ok((light, mut lantern, material_handle, mut rng)) = lantern_query.get_mut(entity)
+        else {
+            continue;
+        };
+
+        match click.button {
+            PointerButton::Primary => {
+                interaction.selected = Some(entity);
+
+                if let (Some(intensity), Some(material)) = (
+                    light_intensity(light),
+                    materials.get_mut(&material_handle.0),
+                ) {
+                    if lantern.is_on {
+                        turned_off.write(LanternTurnedOff {
+                            entity,
+                            radiance: lantern.radiance,
+                            intensity: lantern.base_intensity,
+                        });
+                        switch_off(intensity, &mut lantern, material);
+                    } else {
+                        switch_on(intensity, &mut lantern, material, &mut rng, &config);
+                        turned_on.write(LanternTurnedOn {
+                            entity,
+                            radiance: lantern.radiance,
+                            intensity: lantern.base_intensity,
+                        });
+                    }
+                }
+            }
+            PointerButton::Secondary => {
+                despawn_lantern(&mut commands, entity, &mut interaction, &mut spatial_index);
+            }
+            PointerButton::Middle => {}
+        }
+    }
+}
+
+/// Clicking on empty space hangs a new lantern from the ceiling at that point.
+#[allow(clippy::too_many_arguments)]
+pub fn lantern_placement_system(
+    mut commands: Commands,
+    mut click_events: EventReader<Pointer<Click>>,
+    window_query: Query<(), With<Window>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    ceiling_query: Query<&Transform, With<Ceiling>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut interaction: ResMut<LanternInteraction>,
+    mut spatial_index: ResMut<SpatialIndex>,
+    mut lantern_ids: ResMut<NextLanternId>,
+    mut rng: GlobalEntropy<WyRand>,
+    config: Res<LanternConfig>,
+    bounds: Res<RoomBounds>,
+) {
+    let (Ok((camera, camera_transform)), Ok(ceiling_transform)) =
+        (camera_query.single(), ceiling_query.single())
+    else {
+        return;
+    };
+
+    for click in click_events.read() {
+        if click.button != PointerButton::Primary
+            || !window_query.contains(click.target)
+            || interaction.dragging.contains(&click.target)
+        {
+            continue;
+        }
+
+        let Ok(ray) = camera.viewport_to_world(camera_transform, click.pointer_location.position)
+        else {
+            continue;
+        };
+        let Some(distance) =
+            ray.intersect_plane(ceiling_transform.translation, InfinitePlane3d::new(Vec3::Y))
+        else {
+            continue;
+        };
+
+        let hit = ray.get_point(distance);
+        let horizontal = Vec2::new(hit.x, hit.z).clamp_length_max(bounds.radius);
+        let position = Vec3::new(
+            horizontal.x,
+            ceiling_transform.translation.y - rng.random_range(1.0..5.0),
+            horizontal.y,
+        );
+
+        let entity = spawn_lantern(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            lantern_ids.issue(),
+            position,
+            config.fixture,
+            &mut rng,
+        );
+        spatial_index.insert(entity, position.xz());
+        interaction.selected = Some(entity);
+    }
+}
+
+/// Dragging a lantern moves it in the plane facing the camera.
+#[allow(clippy::too_many_arguments)]
+pub fn lantern_drag_system(
+    mut drag_start_events: EventReader<Pointer<DragStart>>,
+    mut drag_events: EventReader<Pointer<Drag>>,
+    mut drag_end_events: EventReader<Pointer<DragEnd>>,
+    mut lantern_query: Query<(&mut Transform, &mut LanternBob), With<Lantern>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    ceiling_query: Query<&Transform, (With<Ceiling>, Without<Lantern>)>,
+    mut interaction: ResMut<LanternInteraction>,
+    mut spatial_index: ResMut<SpatialIndex>,
+    bounds: Res<RoomBounds>,
+) {
+    for ev in drag_start_events.read() {
+        interaction.dragging.insert(ev.target);
+    }
+
+    if let (Ok((camera, camera_transform)), Ok(ceiling_transform)) =
+        (camera_query.single(), ceiling_query.single())
+    {
+        for ev in drag_events.read() {
+            if ev.button != PointerButton::Primary {
+                continue;
+            }
+            let Ok((mut transform, mut bob)) = lantern_query.get_mut(ev.target) else {
+                continue;
+            };
+            let Ok(ray) = camera.viewport_to_world(camera_transform, ev.pointer_location.position)
+            else {
+                continue;
+            };
+            let Some(distance) = ray.intersect_plane(
+                transform.translation,
+                InfinitePlane3d::new(camera_transform.forward()),
+            ) else {
+                continue;
+            };
+
+            let target = ray.get_point(distance);
+            let horizontal = Vec2::new(target.x, target.z).clamp_length_max(bounds.radius);
+            transform.translation.x = horizontal.x;
+            transform.translation.z = horizontal.y;
+
+            // The bob system owns the y position, so shift its rest height instead
+            bob.initial_y = (bob.initial_y + target.y - transform.translation.y)
+                .clamp(0.0, ceiling_transform.translation.y);
+
+            spatial_index.insert(ev.target, transform.translation.xz());
+        }
+    }
+
+    for ev in drag_end_events.read() {
+        interaction.dragging.remove(&ev.target);
+    }
+}
+
+pub fn lantern_delete_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut interaction: ResMut<LanternInteraction>,
+    mut spatial_index: ResMut<SpatialIndex>,
+) {
+    if !keys.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        return;
+    }
+    if let Some(entity) = interaction.selected {
+        despawn_lantern(&mut commands, entity, &mut interaction, &mut spatial_index);
+    }
+}
+
+pub fn selected_lantern_gizmo_system(
+    mut gizmos: Gizmos,
+    interaction: Res<LanternInteraction>,
+    lantern_query: Query<&Transform, With<Lantern>>,
+) {
+    if let Some(transform) = interaction
+        .selected
+        .and_then(|entity| lantern_query.get(entity).ok())
+    {
+        gizmos.cuboid(
+            Transform::from_translation(transform.translation).with_scale(Vec3::new(0.7, 1.2, 0.7)),
+            Color::srgb(0.4, 0.8, 1.0),
+        );
+    }
+}
+
+/// Clicking a moth selects it. `Tab` and `Shift+Tab` cycle through the moths
+/// and `Escape` clears the selection.
+pub fn moth_selection_system(
+    mut click_events: EventReader<Pointer<Click>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    moth_query: Query<Entity, With<Moth>>,
+    mut selection: ResMut<MothSelection>,
+) {
+    for click in click_events.read() {
+        if click.button == PointerButton::Primary && moth_query.contains(click.target) {
+            selection.selected = Some(click.target);
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Tab) {
+        let mut moths: Vec<Entity> = moth_query.iter().collect();
+        moths.sort();
+
+        let current = selection
+            .selected
+            .and_then(|selected| moths.iter().position(|moth| *moth == selected));
+        let backwards = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let next = match current {
+            Some(index) if backwards => (index + moths.len() - 1) % moths.len(),
+            Some(index) => (index + 1) % moths.len(),
+            None => 0,
+        };
+        selection.selected = moths.get(next).copied();
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        selection.selected = None;
+    }
+
+    if let Some(selected) = selection.selected
+        && !moth_query.contains(selected)
+    {
+        selection.selected = None;
+    }
+}
@@ -1,95 +1,212 @@
 use crate::components::{Lantern, LanternBob};
 use crate::config::LanternConfig;
+use crate::events::{LanternFlickered, LanternTurnedOff, LanternTurnedOn};
+use crate::resources::SpatialIndex;
 use bevy::prelude::*;
-use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use bevy_rand::prelude::{Entropy, WyRand};
 use rand::Rng;
 
-const BASE_EMISSIVE_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
+pub const BASE_EMISSIVE_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
 
+/// The switching chances in `LanternConfig` are per tick at this rate.
+const CHANCE_TICK_RATE: f64 = 60.0;
+
+/// Scales a per-tick chance to the simulated time that passed this frame, so
+/// lanterns switch at the same rate whatever the frame rate or speed.
+fn chance_this_frame(chance_per_tick: f64, time: &Time) -> f64 {
+    1.0 - (1.0 - chance_per_tick).powf(time.delta_secs_f64() * CHANCE_TICK_RATE)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_active_lantern(
-    light: &mut PointLight,
+    entity: Entity,
+    intensity: &mut f32,
     lantern: &mut Lantern,
     material: &mut StandardMaterial,
-    rng: &mut GlobalEntropy<WyRand>,
+    rng: &mut Entropy<WyRand>,
     time: &Time,
     config: &LanternConfig,
+    flickered: &mut EventWriter<LanternFlickered>,
+    turned_off: &mut EventWriter<LanternTurnedOff>,
 ) {
     lantern.on_timer.tick(time.delta());
 
-    if rng.random_bool(config.flicker_chance) {
+    if rng.random_bool(chance_this_frame(config.flicker_chance, time)) {
         let flicker_amount = lantern.base_intensity * 0.5;
         let flicker = rng.random_range(-flicker_amount..flicker_amount);
-        light.intensity = (lantern.base_intensity + flicker).max(0.0);
+        *intensity = (lantern.base_intensity + flicker).max(0.0);
+        flickered.write(LanternFlickered {
+            entity,
+            radiance: lantern.radiance,
+            intensity: *intensity,
+        });
     } else {
-        light.intensity = lantern.base_intensity;
+        *intensity = lantern.base_intensity;
     }
 
     let emissive_factor = if lantern.base_intensity > 0.0 {
-        config.emissive_multiplier * (light.intensity / lantern.base_intensity)
+        config.emissive_multiplier * (*intensity / lantern.base_intensity)
     } else {
         0.0
     };
     material.emissive = BASE_EMISSIVE_COLOR.to_linear() * emissive_factor;
 
     if lantern.on_timer.finished() {
-        lantern.is_on = false;
-        lantern.cooldown.reset();
-        light.intensity = 0.0;
-        lantern.base_intensity = 0.0;
-        material.emissive = Color::BLACK.to_linear();
+        turned_off.write(LanternTurnedOff {
+            entity,
+            radiance: lantern.radiance,
+            intensity: lantern.base_intensity,
+        });
+        switch_off(intensity, lantern, material);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_inactive_lantern(
-    light: &mut PointLight,
+    entity: Entity,
+    intensity: &mut f32,
     lantern: &mut Lantern,
     material: &mut StandardMaterial,
-    rng: &mut GlobalEntropy<WyRand>,
+    rng: &mut Entropy<WyRand>,
     time: &Time,
     config: &LanternConfig,
+    turned_on: &mut EventWriter<LanternTurnedOn>,
 ) {
     lantern.cooldown.tick(time.delta());
 
-    if lantern.cooldown.finished() && rng.random_bool(config.on_chance) {
-        lantern.is_on = true;
-        lantern.on_timer.reset();
-        lantern.radiance = rng.random_range(5.0..=15.0);
-        let new_intensity = rng.random_range(5000.0..15000.0);
-        lantern.base_intensity = new_intensity;
-        light.intensity = new_intensity;
-        material.emissive = BASE_EMISSIVE_COLOR.to_linear() * config.emissive_multiplier;
+    if lantern.cooldown.finished() && rng.random_bool(chance_this_frame(config.on_chance, time)) {
+        switch_on(intensity, lantern, material, rng, config);
+        turned_on.write(LanternTurnedOn {
+            entity,
+            radiance: lantern.radiance,
+            intensity: lantern.base_intensity,
+        });
+    }
+}
+
+/// Turns a lantern on with a radiance and intensity freshly rolled from its
+/// own generator.
+pub fn switch_on(
+    intensity: &mut f32,
+    lantern: &mut Lantern,
+    material: &mut StandardMaterial,
+    rng: &mut Entropy<WyRand>,
+    config: &LanternConfig,
+) {
+    lantern.is_on = true;
+    lantern.on_timer.reset();
+    lantern.radiance = rng.random_range(5.0..=15.0);
+    let new_intensity = rng.random_range(5000.0..15000.0);
+    lantern.base_intensity = new_intensity;
+    *intensity = new_intensity;
+    material.emissive = BASE_EMISSIVE_COLOR.to_linear() * config.emissive_multiplier;
+}
+
+/// Turns a lantern off and starts its cooldown.
+pub fn switch_off(intensity: &mut f32, lantern: &mut Lantern, material: &mut StandardMaterial) {
+    lantern.is_on = false;
+    lantern.cooldown.reset();
+    *intensity = 0.0;
+    lantern.base_intensity = 0.0;
+    material.emissive = Color::BLACK.to_linear();
+}
+
+/// A lantern's light, a point or a spot light depending on its fixture.
+pub type LanternLight = AnyOf<(&'static mut PointLight, &'static mut SpotLight)>;
+
+/// Everything switching a lantern on or off touches: its light, its state, its
+/// material and the generator it rolls from.
+pub type SwitchableLantern = (
+    LanternLight,
+    &'static mut Lantern,
+    &'static MeshMaterial3d<StandardMaterial>,
+    &'static mut Entropy<WyRand>,
+);
+
+/// Lanterns carry either a point or a spot light depending on their fixture.
+/// Switching and flickering only ever touch the intensity, so both are
+/// handled through it.
+pub fn light_intensity<'a>(
+    (point_light, spot_light): (Option<Mut<'a, PointLight>>, Option<Mut<'a, SpotLight>>),
+) -> Option<&'a mut f32> {
+    match (point_light, spot_light) {
+        (Some(light), _) => Some(&mut light.into_inner().intensity),
+        (_, Some(light)) => Some(&mut light.into_inner().intensity),
+        _ => None,
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn lantern_power_system(
-    mut lantern_query: Query<(
-        &mut PointLight,
-        &mut Lantern,
-        &mut MeshMaterial3d<StandardMaterial>,
-    )>,
+    mut lantern_query: Query<(Entity, SwitchableLantern)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     time: Res<Time>,
-    mut rng: GlobalEntropy<WyRand>,
     config: Res<LanternConfig>,
+    mut turned_on: EventWriter<LanternTurnedOn>,
+    mut turned_off: EventWriter<LanternTurnedOff>,
+    mut flickered: EventWriter<LanternFlickered>,
 ) {
-    for (mut light, mut lantern, mut material_handle) in lantern_query.iter_mut() {
-        if let Some(material) = materials.get_mut(&mut material_handle.0) {
+    // Each lantern draws from its own generator, so the outcome doesn't depend
+    // on the order lanterns are visited in
+    for (entity, (light, mut lantern, material_handle, mut rng)) in lantern_query.iter_mut() {
+        if let (Some(intensity), Some(material)) = (
+            light_intensity(light),
+            materials.get_mut(&material_handle.0),
+        ) {
             if lantern.is_on {
-                handle_active_lantern(&mut light, &mut lantern, material, &mut rng, &time, &config);
+                handle_active_lantern(
+                    entity,
+                    intensity,
+                    &mut lantern,
+                    material,
+                    &mut rng,
+                    &time,
+                    &config,
+                    &mut flickered,
+                    &mut turned_off,
+                );
             } else {
                 handle_inactive_lantern(
-                    &mut light,
+                    entity,
+                    intensity,
                     &mut lantern,
                     material,
                     &mut rng,
                     &time,
                     &config,
+                    &mut turned_on,
                 );
             }
         }
     }
 }
 
+/// Logs lantern state changes at debug level.
+pub fn lantern_event_log_system(
+    mut turned_on: EventReader<LanternTurnedOn>,
+    mut turned_off: EventReader<LanternTurnedOff>,
+    mut flickered: EventReader<LanternFlickered>,
+) {
+    for event in turned_on.read() {
+        debug!(
+            "Lantern {} turned on with radiance {} and intensity {}",
+            event.entity, event.radiance, event.intensity
+        );
+    }
+    for event in turned_off.read() {
+        debug!(
+            "Lantern {} turned off after running at radiance {} and intensity {}",
+            event.entity, event.radiance, event.intensity
+        );
+    }
+    for event in flickered.read() {
+        debug!(
+            "Lantern {} flickered to intensity {} at radiance {}",
+            event.entity, event.intensity, event.radiance
+        );
+    }
+}
+
 pub fn lantern_bob_system(
     time: Res<Time>,
     mut query: Query<(&mut Transform, &LanternBob)>,
@@ -101,3 +218,25 @@ pub fn lantern_bob_system(
                 * config.bob_amplitude;
     }
 }
+
+/// Lanterns whose transform changed since the system last ran.
+type MovedLantern = (With<Lantern>, Changed<Transform>);
+
+/// Keeps lanterns in the right index cell as they bob or get moved, and drops
+/// despawned ones. A lantern despawned by a command can still be moved in the
+/// frame it goes, so this catches the entries that puts back.
+pub fn lantern_index_system(
+    mut spatial_index: ResMut<SpatialIndex>,
+    lantern_query: Query<(Entity, &Transform), MovedLantern>,
+    mut removed: RemovedComponents<Lantern>,
+) {
+    for entity in removed.read() {
+        spatial_index.remove(entity);
+    }
+    for (entity, transform) in lantern_query.iter() {
+        let pos = transform.translation.xz();
+        if spatial_index.cell_of(entity) != Some(SpatialIndex::tile(pos)) {
+            spatial_index.insert(entity, pos);
+        }
+    }
+}
@@ -1,7 +1,33 @@
+pub mod benchmark;
+pub mod debug;
+pub mod files;
+pub mod heatmap;
+pub mod inspector;
+pub mod interaction;
 pub mod lantern;
 pub mod moth;
+pub mod recording;
+pub mod replay;
 pub mod scene;
+pub mod share;
+pub mod snapshot;
+pub mod time;
+pub mod trail;
+pub mod trap;
 
+pub use benchmark::*;
+pub use debug::*;
+pub use files::*;
+pub use heatmap::*;
+pub use inspector::*;
+pub use interaction::*;
 pub use lantern::*;
 pub use moth::*;
+pub use recording::*;
+pub use replay::*;
 pub use scene::*;
+pub use share::*;
+pub use snapshot::*;
+pub use time::*;
+pub use trail::*;
+pub use trap::*;
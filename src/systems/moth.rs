@@ -1,109 +1,291 @@
-use crate::components::{Lantern, Moth, Velocity};
+use crate::components::{
+    Lantern, Moth, MothPerception, MothState, MothStats, Perch, Steering, Velocity,
+};
 use crate::config::{LanternConfig, MothConfig};
 use bevy::prelude::*;
-use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use bevy::render::mesh::MeshTag;
+use bevy_rand::prelude::{Entropy, WyRand};
 use rand::Rng;
+use std::f32::consts::TAU;
+
+/// A perch for a moth that touched a surface facing `normal`, if it was
+/// flying into the surface slowly enough to land.
+pub fn perch_on(
+    velocity: Vec3,
+    normal: Vec3,
+    moth_config: &MothConfig,
+    rng: &mut Entropy<WyRand>,
+) -> Option<Perch> {
+    let impact_speed = -velocity.dot(normal);
+    if impact_speed <= 0.0 || impact_speed >= moth_config.perch_speed {
+        return None;
+    }
+    // The range can be set from outside, so a backwards one rests for the minimum
+    let min_secs = moth_config.min_perch_secs.max(0.0);
+    let secs = rng.random_range(min_secs..=moth_config.max_perch_secs.max(min_secs));
+    Some(Perch {
+        normal,
+        lantern: None,
+        offset: Vec3::ZERO,
+        timer: Timer::from_seconds(secs, TimerMode::Once),
+    })
+}
+
+/// Moths in the air, which every flight system moves and none of them touch
+/// once they've perched.
+pub type Flying = (With<Moth>, Without<Perch>);
+
+/// What landing needs from a moth that may have hit a surface.
+pub type LandingMoth = (
+    Entity,
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static mut MothState,
+    &'static mut Entropy<WyRand>,
+);
+
+/// What holding a moth on its perch reads and writes.
+type PerchedMoth = (
+    Entity,
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static mut MothState,
+    &'static mut Perch,
+);
+
+/// Stops a moth on `perch`, lying flat against the surface and facing the way
+/// it was flying.
+pub fn land(
+    moth: Entity,
+    perch: Perch,
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+    state: &mut MothState,
+    commands: &ParallelCommands,
+) {
+    let heading = velocity.0.reject_from_normalized(perch.normal);
+    let heading = if heading.length_squared() > 1e-6 {
+        heading
+    } else {
+        perch.normal.any_orthonormal_vector()
+    };
+    transform.look_to(heading, perch.normal);
+    velocity.0 = Vec3::ZERO;
+    *state = MothState::Perched;
+    commands.command_scope(|mut commands| {
+        commands.entity(moth).insert(perch);
+    });
+}
+
+/// Holds perched moths on their surface, moving them with any lantern they're
+/// on, and sends them off along the surface normal once their rest is over.
+pub fn moth_perch_system(
+    mut commands: Commands,
+    mut moth_query: Query<PerchedMoth, With<Moth>>,
+    lantern_query: Query<&Transform, (With<Lantern>, Without<Moth>)>,
+    moth_config: Res<MothConfig>,
+    time: Res<Time>,
+) {
+    for (moth, mut transform, mut velocity, mut state, mut perch) in &mut moth_query {
+        let lantern = perch.lantern.map(|lantern| lantern_query.get(lantern));
+        if let Some(Ok(lantern_transform)) = lantern {
+            transform.translation = lantern_transform.translation + perch.offset;
+        }
+
+        // A moth whose lantern was removed has nothing left to sit on
+        perch.timer.tick(time.delta());
+        if perch.timer.finished() || matches!(lantern, Some(Err(_))) {
+            velocity.0 = perch.normal * moth_config.moth_speed;
+            *state = MothState::Wandering;
+            commands.entity(moth).remove::<Perch>();
+        }
+    }
+}
 
 pub fn moth_wander_system(
-    mut moth_query: Query<&mut Velocity, With<Moth>>,
+    mut moth_query: Query<(&mut Velocity, &mut Steering, &mut Entropy<WyRand>), Flying>,
     moth_config: Res<MothConfig>,
-    mut rng: GlobalEntropy<WyRand>,
 ) {
-    for mut velocity in moth_query.iter_mut() {
-        if velocity.0 == Vec3::ZERO {
-            velocity.0 = Vec3::new(
+    moth_query
+        .par_iter_mut()
+        .for_each(|(mut velocity, mut steering, mut rng)| {
+            if velocity.0 == Vec3::ZERO {
+                velocity.0 = Vec3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                )
+                .normalize_or_zero()
+                    * moth_config.moth_speed;
+            }
+
+            let jitter = Vec3::new(
                 rng.random_range(-1.0..1.0),
                 rng.random_range(-1.0..1.0),
                 rng.random_range(-1.0..1.0),
             )
             .normalize_or_zero()
-                * moth_config.moth_speed;
-        }
-
-        let jitter = Vec3::new(
-            rng.random_range(-1.0..1.0),
-            rng.random_range(-1.0..1.0),
-            rng.random_range(-1.0..1.0),
-        )
-        .normalize_or_zero()
-            * 0.15;
+                * 0.15;
 
-        velocity.0 = (velocity.0 + jitter).normalize_or_zero() * moth_config.moth_speed;
-    }
+            velocity.0 = (velocity.0 + jitter).normalize_or_zero() * moth_config.moth_speed;
+            steering.wander = jitter;
+        });
 }
 
 use crate::resources::SpatialIndex;
 
+/// What the attraction update reads from a moth and writes back to it.
+type AttractedMoth = (
+    &'static Transform,
+    &'static mut Velocity,
+    &'static mut MothPerception,
+    &'static mut MothState,
+    &'static mut MothStats,
+    &'static mut Steering,
+);
+
 pub fn moth_attraction_system(
     moth_config: Res<MothConfig>,
-    mut moth_query: Query<(&Transform, &mut Velocity), With<Moth>>,
+    mut moth_query: Query<AttractedMoth, Flying>,
     lantern_query: Query<(Entity, &Transform, &Lantern)>,
     time: Res<Time>,
     spatial_index: Res<SpatialIndex>,
 ) {
-    for (moth_transform, mut velocity) in moth_query.iter_mut() {
-        let nearby_lanterns = spatial_index.get_nearby(moth_transform.translation.xy());
-        let mut total_attraction_force = Vec3::ZERO;
-
-        for lantern_entity in nearby_lanterns {
-            if let Ok((_, lantern_transform, lantern)) = lantern_query.get(lantern_entity) {
-                if !lantern.is_on {
-                    continue;
-                }
+    moth_query.par_iter_mut().for_each(
+        |(moth_transform, mut velocity, mut perception, mut state, mut stats, mut steering)| {
+            let nearby_lanterns = spatial_index.get_nearby(moth_transform.translation.xz());
+            let mut total_attraction_force = Vec3::ZERO;
+            let mut strongest_light: Option<(Entity, f32)> = None;
+
+            for lantern_entity in nearby_lanterns {
+                if let Ok((_, lantern_transform, lantern)) = lantern_query.get(lantern_entity) {
+                    if !lantern.is_on {
+                        continue;
+                    }
 
-                let to_lantern = lantern_transform.translation - moth_transform.translation;
-                let dist_sq = to_lantern.length_squared();
+                    let to_lantern = lantern_transform.translation - moth_transform.translation;
+                    if !lantern.fixture.emits_towards(-to_lantern) {
+                        continue;
+                    }
 
-                if dist_sq < moth_config.view_radius.powi(2) {
-                    let strength = lantern.radiance / (dist_sq + 1.0);
-                    total_attraction_force += to_lantern.normalize_or_zero() * strength;
+                    let dist_sq = to_lantern.length_squared();
+
+                    if dist_sq < moth_config.view_radius.powi(2) {
+                        let strength = lantern.radiance / (dist_sq + 1.0);
+                        total_attraction_force += to_lantern.normalize_or_zero() * strength;
+                        if strongest_light.is_none_or(|(_, strongest)| strength > strongest) {
+                            strongest_light = Some((lantern_entity, strength));
+                        }
+                    }
                 }
             }
-        }
 
-        if total_attraction_force.length_squared() > 0.0 {
             let acceleration = total_attraction_force * moth_config.attraction_factor;
-            velocity.0 += acceleration * time.delta_secs();
-        }
-    }
+            if total_attraction_force.length_squared() > 0.0 {
+                velocity.0 += acceleration * time.delta_secs();
+            }
+
+            perception.strongest_light = strongest_light.map(|(entity, _)| entity);
+            steering.attraction = acceleration;
+            if strongest_light.is_some() {
+                *state = MothState::Attracted;
+                stats.time_near_lights += time.delta_secs();
+            } else {
+                *state = MothState::Wandering;
+            }
+        },
+    );
 }
 
 pub fn moth_movement_system(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Moth>>,
+    mut query: Query<(&mut Transform, &mut Velocity), Flying>,
     time: Res<Time>,
     moth_config: Res<MothConfig>,
 ) {
-    for (mut transform, mut velocity) in &mut query {
-        velocity.0 = velocity.0.clamp_length_max(moth_config.moth_speed);
-        transform.translation += velocity.0 * time.delta_secs();
-        if velocity.0 != Vec3::ZERO {
-            transform.look_to(velocity.0, Vec3::Y);
-        }
-    }
+    query
+        .par_iter_mut()
+        .for_each(|(mut transform, mut velocity)| {
+            velocity.0 = velocity.0.clamp_length_max(moth_config.moth_speed);
+            transform.translation += velocity.0 * time.delta_secs();
+            if velocity.0 != Vec3::ZERO {
+                transform.look_to(velocity.0, Vec3::Y);
+            }
+        });
 }
 
+/// Pushes moths back out of lanterns, landing those that flew in gently.
 pub fn moth_collision_system(
-    mut moth_query: Query<(&mut Transform, &mut Velocity), With<Moth>>,
+    commands: ParallelCommands,
+    mut moth_query: Query<LandingMoth, Flying>,
     lantern_query: Query<&Transform, (With<Lantern>, Without<Moth>)>,
+    moth_config: Res<MothConfig>,
     lantern_config: Res<LanternConfig>,
     spatial_index: Res<SpatialIndex>,
 ) {
-    for (mut moth_transform, mut velocity) in moth_query.iter_mut() {
-        let nearby_lanterns = spatial_index.get_nearby(moth_transform.translation.xy());
-
-        for lantern_entity in nearby_lanterns {
-            if let Ok(lantern_transform) = lantern_query.get(lantern_entity) {
-                let distance = moth_transform
-                    .translation
-                    .distance(lantern_transform.translation);
-                if distance < lantern_config.physical_radius {
-                    let direction = (moth_transform.translation - lantern_transform.translation)
-                        .normalize_or_zero();
-                    velocity.0 = direction * velocity.0.length();
-                    moth_transform.translation =
-                        lantern_transform.translation + direction * lantern_config.physical_radius;
+    moth_query.par_iter_mut().for_each(
+        |(moth, mut moth_transform, mut velocity, mut state, mut rng)| {
+            let nearby_lanterns = spatial_index.get_nearby(moth_transform.translation.xz());
+
+            for lantern_entity in nearby_lanterns {
+                if let Ok(lantern_transform) = lantern_query.get(lantern_entity) {
+                    let distance = moth_transform
+                        .translation
+                        .distance(lantern_transform.translation);
+                    if distance < lantern_config.physical_radius {
+                        let direction = (moth_transform.translation
+                            - lantern_transform.translation)
+                            .normalize_or_zero();
+                        moth_transform.translation = lantern_transform.translation
+                            + direction * lantern_config.physical_radius;
+
+                        if let Some(perch) = perch_on(velocity.0, direction, &moth_config, &mut rng)
+                        {
+                            let perch = Perch {
+                                lantern: Some(lantern_entity),
+                                offset: direction * lantern_config.physical_radius,
+                                ..perch
+                            };
+                            land(
+                                moth,
+                                perch,
+                                &mut moth_transform,
+                                &mut velocity,
+                                &mut state,
+                                &commands,
+                            );
+                            break;
+                        }
+                        velocity.0 = direction * velocity.0.length();
+                    }
                 }
             }
-        }
-    }
+        },
+    );
+}
+
+/// Advances each moth's wingbeat, kept as the phase in its `MeshTag` for the
+/// moth shader. Wingbeats speed up and slow down with the moth, and perched
+/// moths rest with their wings out flat.
+pub fn wing_flap_system(
+    mut moth_query: Query<(&Velocity, &MothState, &mut MeshTag), With<Moth>>,
+    moth_config: Res<MothConfig>,
+    time: Res<Time>,
+) {
+    let radians_per_metre = if moth_config.moth_speed > 0.0 {
+        TAU * moth_config.wingbeat_hz / moth_config.moth_speed
+    } else {
+        0.0
+    };
+    let step = radians_per_metre * time.delta_secs();
+
+    moth_query
+        .par_iter_mut()
+        .for_each(|(velocity, state, mut tag)| {
+            let phase = if *state == MothState::Perched {
+                0.0
+            } else {
+                (f32::from_bits(tag.0) + step * velocity.0.length()) % TAU
+            };
+            tag.set_if_neq(MeshTag(phase.to_bits()));
+        });
 }
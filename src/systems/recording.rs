@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use crate::components::{Lantern, Moth};
+use crate::config::RecordingConfig;
+use crate::events::{LanternTurnedOff, LanternTurnedOn};
+use crate::recording::{LanternEventKind, RecordedEvent, RecordedFrame, RecordedLantern};
+use crate::resources::Recorder;
+
+/// Logs lantern switches at the time of the frame they happened in. Runs
+/// before `record_frame_system` so both see the same recording time.
+pub fn record_events_system(
+    mut recorder: ResMut<Recorder>,
+    mut turned_on: EventReader<LanternTurnedOn>,
+    mut turned_off: EventReader<LanternTurnedOff>,
+) {
+    if !recorder.active {
+        turned_on.clear();
+        turned_off.clear();
+        return;
+    }
+
+    let recorder = recorder.as_mut();
+    let switches = turned_on
+        .read()
+        .map(|ev| (ev.entity, LanternEventKind::TurnedOn))
+        .chain(
+            turned_off
+                .read()
+                .map(|ev| (ev.entity, LanternEventKind::TurnedOff)),
+        );
+    for (entity, kind) in switches {
+        recorder.recording.events.push(RecordedEvent {
+            time: recorder.elapsed,
+            lantern: Recorder::id(&mut recorder.lantern_ids, entity),
+            kind,
+        });
+    }
+}
+
+/// What a recording frame reads from a lantern.
+type SampledLantern = (
+    Entity,
+    &'static Transform,
+    &'static Lantern,
+    AnyOf<(&'static PointLight, &'static SpotLight)>,
+);
+
+/// Samples moth and lantern positions every `sample_interval` of simulated
+/// time.
+pub fn record_frame_system(
+    mut recorder: ResMut<Recorder>,
+    moth_query: Query<(Entity, &Transform), With<Moth>>,
+    lantern_query: Query<SampledLantern>,
+    config: Res<RecordingConfig>,
+    time: Res<Time>,
+) {
+    if !recorder.active {
+        return;
+    }
+
+    let recorder = recorder.as_mut();
+    if recorder.recording.frames.is_empty() || recorder.since_sample >= config.sample_interval {
+        recorder.since_sample = 0.0;
+
+        let mut moths: Vec<_> = moth_query
+            .iter()
+            .map(|(entity, transform)| {
+                (
+                    Recorder::id(&mut recorder.moth_ids, entity),
+                    transform.translation,
+                )
+            })
+            .collect();
+        moths.sort_unstable_by_key(|(id, _)| *id);
+
+        let mut lanterns: Vec<_> = lantern_query
+            .iter()
+            .map(|(entity, transform, lantern, (point_light, spot_light))| {
+                let intensity = point_light
+                    .map(|light| light.intensity)
+                    .or(spot_light.map(|light| light.intensity))
+                    .unwrap_or_default();
+                RecordedLantern {
+                    id: Recorder::id(&mut recorder.lantern_ids, entity),
+                    position: transform.translation,
+                    fixture: lantern.fixture,
+                    intensity,
+                }
+            })
+            .collect();
+        lanterns.sort_unstable_by_key(|lantern| lantern.id);
+
+        recorder.recording.frames.push(RecordedFrame {
+            time: recorder.elapsed,
+            moths,
+            lanterns,
+        });
+    }
+
+    recorder.elapsed += time.delta_secs();
+    recorder.since_sample += time.delta_secs();
+}
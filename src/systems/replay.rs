@@ -0,0 +1,425 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy_rand::prelude::{GlobalEntropy, WyRand};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::components::{
+    Lantern, Moth, ReplayButton, ReplayLabel, ReplayPanel, ReplayScrubber, ReplayScrubberFill,
+    Velocity,
+};
+use crate::config::{LanternConfig, RecordingConfig};
+use crate::recording::{RecordedFrame, Recording};
+use crate::resources::{
+    LanternInteraction, MothAssets, MothSelection, NextLanternId, Recorder, ReplayPlayer,
+    ReplayRequests, SpatialIndex,
+};
+use crate::setup::{BUTTON_COLOUR, BUTTON_HOVER_COLOUR, moth_bundle, spawn_lantern};
+use crate::snapshot::{restore_snapshot, save_snapshot};
+use crate::systems::{
+    BASE_EMISSIVE_COLOR, LanternLight, light_intensity, request_text_file, save_text_file,
+};
+
+/// Run condition for the systems that drive the live simulation, which stand
+/// down while a recording plays.
+pub fn replaying(player: Res<ReplayPlayer>) -> bool {
+    player.recording.is_some()
+}
+
+fn apply_replay_control(
+    button: ReplayButton,
+    player: &mut ReplayPlayer,
+    virtual_time: &mut Time<Virtual>,
+) {
+    let Some(recording) = &player.recording else {
+        return;
+    };
+
+    match button {
+        ReplayButton::Rewind => player.time = 0.0,
+        ReplayButton::PreviousEvent => {
+            if let Some(event) = recording.previous_turn_on(player.time) {
+                player.time = event.time;
+            }
+        }
+        ReplayButton::TogglePause => {
+            if virtual_time.is_paused() {
+                // Playing from the end starts again from the beginning
+                if player.time >= recording.duration() {
+                    player.time = 0.0;
+                }
+                virtual_time.unpause();
+            } else {
+                virtual_time.pause();
+            }
+        }
+        ReplayButton::NextEvent => {
+            if let Some(event) = recording.next_turn_on(player.time) {
+                player.time = event.time;
+            }
+        }
+        // `replay_close_system` puts the live simulation back
+        ReplayButton::Close => player.recording = None,
+    }
+}
+
+/// `R` starts and stops recording. `Shift+R` opens a recording for playback,
+/// or closes the one playing. While playing, `Home` rewinds and
+/// `PageUp`/`PageDown` jump between lanterns turning on.
+pub fn replay_keys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut recorder: ResMut<Recorder>,
+    mut player: ResMut<ReplayPlayer>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    requests: Res<ReplayRequests>,
+    config: Res<RecordingConfig>,
+) {
+    if keys.just_pressed(KeyCode::KeyR) {
+        if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            if player.recording.is_some() {
+                player.recording = None;
+            } else {
+                request_text_file(&config.path, &requests.load);
+            }
+        } else if recorder.active {
+            match recorder.stop().to_ron() {
+                Ok(text) => save_text_file(&config.path, &text),
+                Err(err) => warn!("Failed to save recording: {err}"),
+            }
+        } else if player.recording.is_none() {
+            recorder.start();
+            info!("Recording started");
+        }
+    }
+
+    for (key, button) in [
+        (KeyCode::Home, ReplayButton::Rewind),
+        (KeyCode::PageUp, ReplayButton::PreviousEvent),
+        (KeyCode::PageDown, ReplayButton::NextEvent),
+    ] {
+        if keys.just_pressed(key) {
+            apply_replay_control(button, &mut player, &mut virtual_time);
+        }
+    }
+}
+
+/// The moths and lanterns a replay swaps out.
+type Simulated = Or<(With<Moth>, With<Lantern>)>;
+
+/// Plays a newly loaded recording, saving the live simulation first so
+/// closing the replay can put it back.
+pub fn replay_load_system(world: &mut World) {
+    let Some(text) = world
+        .resource::<ReplayRequests>()
+        .load
+        .lock()
+        .unwrap()
+        .take()
+    else {
+        return;
+    };
+    let recording = match Recording::from_ron(&text) {
+        Ok(recording) if !recording.frames.is_empty() => recording,
+        Ok(_) => {
+            warn!("Recording has no frames");
+            return;
+        }
+        Err(err) => {
+            warn!("Failed to load recording: {err}");
+            return;
+        }
+    };
+
+    // Loading over a recording that's already playing keeps the simulation
+    // saved before the first one
+    if world.resource::<ReplayPlayer>().saved_scene.is_none() {
+        match save_snapshot(world) {
+            Ok(scene) => world.resource_mut::<ReplayPlayer>().saved_scene = Some(scene),
+            Err(err) => {
+                warn!("Failed to save the simulation before playback: {err}");
+                return;
+            }
+        }
+    }
+
+    if let Err(err) = world.run_system_cached_with(swap_in_recording, recording) {
+        warn!("Failed to start playback: {err}");
+    }
+}
+
+/// Swaps the live moths and lanterns for stand-ins driven by `recording`.
+#[allow(clippy::too_many_arguments)]
+fn swap_in_recording(
+    In(recording): In<Recording>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut player: ResMut<ReplayPlayer>,
+    mut recorder: ResMut<Recorder>,
+    moth_assets: Res<MothAssets>,
+    existing_query: Query<Entity, Simulated>,
+    mut spatial_index: ResMut<SpatialIndex>,
+    mut selection: ResMut<MothSelection>,
+    mut interaction: ResMut<LanternInteraction>,
+    mut lantern_ids: ResMut<NextLanternId>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    if recorder.active {
+        recorder.stop();
+        warn!("Discarded the recording in progress to play one back");
+    }
+    for entity in existing_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spatial_index.clear();
+    selection.selected = None;
+    interaction.selected = None;
+    interaction.dragging.clear();
+
+    // Everything starts hidden and appears in the frames it was recorded in
+    let moth_ids: BTreeSet<u32> = recording
+        .frames
+        .iter()
+        .flat_map(|frame| frame.moths.iter().map(|(id, _)| *id))
+        .collect();
+    let moths: HashMap<u32, Entity> = moth_ids
+        .into_iter()
+        .map(|id| {
+            let moth = moth_bundle(&moth_assets, Vec3::ZERO, Vec3::ZERO, &mut rng);
+            (id, commands.spawn((moth, Visibility::Hidden)).id())
+        })
+        .collect();
+
+    let mut first_seen = BTreeMap::new();
+    for lantern in recording.frames.iter().flat_map(|frame| &frame.lanterns) {
+        first_seen.entry(lantern.id).or_insert(*lantern);
+    }
+    let lanterns: HashMap<u32, Entity> = first_seen
+        .into_values()
+        .map(|lantern| {
+            let entity = spawn_lantern(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                lantern_ids.issue(),
+                lantern.position,
+                lantern.fixture,
+                &mut rng,
+            );
+            commands.entity(entity).insert(Visibility::Hidden);
+            (lantern.id, entity)
+        })
+        .collect();
+
+    info!(
+        "Playing a {:.1} s recording of {} moths",
+        recording.duration(),
+        moths.len()
+    );
+    *player = ReplayPlayer {
+        recording: Some(recording),
+        time: 0.0,
+        moths,
+        lanterns,
+        saved_scene: player.saved_scene.take(),
+    };
+}
+
+/// Once a replay is closed, swaps its stand-ins back out for the simulation
+/// that was running before it was loaded.
+pub fn replay_close_system(world: &mut World) {
+    let player = world.resource::<ReplayPlayer>();
+    if player.recording.is_some() || player.saved_scene.is_none() {
+        return;
+    }
+
+    let player = std::mem::take(&mut *world.resource_mut::<ReplayPlayer>());
+    for entity in player
+        .moths
+        .into_values()
+        .chain(player.lanterns.into_values())
+    {
+        world.despawn(entity);
+    }
+    if let Some(scene) = player.saved_scene
+        && let Err(err) = restore_snapshot(world, &scene)
+    {
+        warn!("Failed to restore the simulation after playback: {err}");
+    }
+}
+
+fn recorded_moth(frame: &RecordedFrame, id: u32) -> Option<Vec3> {
+    frame
+        .moths
+        .binary_search_by_key(&id, |(id, _)| *id)
+        .ok()
+        .map(|index| frame.moths[index].1)
+}
+
+/// What playback poses on a stand-in moth.
+type ReplayedMoth = (
+    &'static mut Transform,
+    &'static mut Velocity,
+    &'static mut Visibility,
+);
+
+/// What playback poses and lights on a stand-in lantern.
+type ReplayedLantern = (
+    &'static mut Transform,
+    &'static mut Lantern,
+    &'static mut Visibility,
+    LanternLight,
+    &'static MeshMaterial3d<StandardMaterial>,
+);
+
+/// Advances playback with simulated time, so pausing and the speed controls
+/// apply, and poses moths and lanterns between the surrounding frames.
+pub fn replay_playback_system(
+    mut player: ResMut<ReplayPlayer>,
+    mut moth_query: Query<ReplayedMoth, (With<Moth>, Without<Lantern>)>,
+    mut lantern_query: Query<ReplayedLantern, Without<Moth>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<LanternConfig>,
+    time: Res<Time>,
+) {
+    let player = player.as_mut();
+    let Some(recording) = &player.recording else {
+        return;
+    };
+    player.time = (player.time + time.delta_secs()).min(recording.duration());
+    let Some((before, after, t)) = recording.frames_around(player.time) else {
+        return;
+    };
+    let span = after.time - before.time;
+
+    for (id, entity) in &player.moths {
+        let Ok((mut transform, mut velocity, mut visibility)) = moth_query.get_mut(*entity) else {
+            continue;
+        };
+        let Some(start) = recorded_moth(before, *id) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let end = recorded_moth(after, *id).unwrap_or(start);
+
+        transform.translation = start.lerp(end, t);
+        if span > 0.0 {
+            velocity.0 = (end - start) / span;
+        }
+        if velocity.0 != Vec3::ZERO {
+            transform.look_to(velocity.0, Vec3::Y);
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+
+    for (id, entity) in &player.lanterns {
+        let Ok((mut transform, mut lantern, mut visibility, light, material_handle)) =
+            lantern_query.get_mut(*entity)
+        else {
+            continue;
+        };
+        let Some(start) = before.lanterns.iter().find(|lantern| lantern.id == *id) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let end = after
+            .lanterns
+            .iter()
+            .find(|lantern| lantern.id == *id)
+            .unwrap_or(start);
+
+        transform.translation = start.position.lerp(end.position, t);
+        visibility.set_if_neq(Visibility::Inherited);
+        if let Some(intensity) = light_intensity(light) {
+            *intensity = start.intensity;
+        }
+
+        let is_on = start.intensity > 0.0;
+        if lantern.is_on != is_on {
+            lantern.is_on = is_on;
+            if let Some(material) = materials.get_mut(&material_handle.0) {
+                material.emissive = if is_on {
+                    BASE_EMISSIVE_COLOR.to_linear() * config.emissive_multiplier
+                } else {
+                    Color::BLACK.to_linear()
+                };
+            }
+        }
+    }
+}
+
+pub fn replay_button_system(
+    mut button_query: Query<
+        (&Interaction, &ReplayButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut player: ResMut<ReplayPlayer>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    for (interaction, button, mut background) in button_query.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                apply_replay_control(*button, &mut player, &mut virtual_time);
+            }
+            Interaction::Hovered => background.0 = BUTTON_HOVER_COLOUR,
+            Interaction::None => background.0 = BUTTON_COLOUR,
+        }
+    }
+}
+
+/// Seeks to wherever the scrubber is pressed, following the pointer while it
+/// is held.
+pub fn replay_scrubber_system(
+    scrubber_query: Query<(&Interaction, &RelativeCursorPosition), With<ReplayScrubber>>,
+    mut player: ResMut<ReplayPlayer>,
+) {
+    let Some(duration) = player.recording.as_ref().map(Recording::duration) else {
+        return;
+    };
+    for (interaction, cursor) in scrubber_query.iter() {
+        if *interaction == Interaction::Pressed
+            && let Some(position) = cursor.normalized
+        {
+            player.time = position.x.clamp(0.0, 1.0) * duration;
+        }
+    }
+}
+
+pub fn replay_ui_system(
+    player: Res<ReplayPlayer>,
+    mut panel_query: Query<&mut Visibility, With<ReplayPanel>>,
+    mut fill_query: Query<&mut Node, With<ReplayScrubberFill>>,
+    mut label_query: Query<&mut Text, With<ReplayLabel>>,
+) {
+    let panel_visibility = match player.recording {
+        Some(_) => Visibility::Inherited,
+        None => Visibility::Hidden,
+    };
+    for mut visibility in panel_query.iter_mut() {
+        visibility.set_if_neq(panel_visibility);
+    }
+
+    let Some(recording) = &player.recording else {
+        return;
+    };
+    let duration = recording.duration();
+    let progress = if duration > 0.0 {
+        player.time / duration
+    } else {
+        0.0
+    };
+
+    let width = Val::Percent(progress * 100.0);
+    for mut node in fill_query.iter_mut() {
+        if node.width != width {
+            node.width = width;
+        }
+    }
+
+    let label = format!("{:.1} / {:.1} s", player.time, duration);
+    for mut text in label_query.iter_mut() {
+        if text.0 != label {
+            text.0 = label.clone();
+        }
+    }
+}
@@ -1,65 +1,155 @@
 use bevy::{
+    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
     input::touch::{TouchInput, TouchPhase},
     prelude::*,
 };
 
-use crate::components::{Moth, OrbitCamera, Velocity};
-use crate::resources::TouchState;
-
-pub fn enforce_boundary_system(mut moth_query: Query<(&mut Transform, &mut Velocity), With<Moth>>) {
-    const ROOM_RADIUS: f32 = 10.0;
-    const CEILING_HEIGHT: f32 = 10.0;
-    const FLOOR_HEIGHT: f32 = 0.0;
-
-    for (mut transform, mut velocity) in moth_query.iter_mut() {
-        let pos = &mut transform.translation;
-
-        // Enforce cylindrical boundary
-        let horizontal_pos = Vec2::new(pos.x, pos.z);
-        if horizontal_pos.length() > ROOM_RADIUS {
-            let normal = horizontal_pos.normalize() * -1.0;
-            let vel_dir = Vec2::new(velocity.0.x, velocity.0.z).normalize();
-            if vel_dir.dot(normal) < 0.0 {
-                let reflect = vel_dir - 2.0 * vel_dir.dot(normal) * normal;
-                velocity.0.x = reflect.x * velocity.0.length();
-                velocity.0.z = reflect.y * velocity.0.length();
+use crate::components::{CameraMode, Lantern, OrbitCamera};
+use crate::config::{CameraConfig, MothConfig};
+use crate::resources::{LanternInteraction, RoomBounds, TouchState};
+use crate::systems::{Flying, LandingMoth, land, perch_on};
+
+/// Keeps moths inside the room, bouncing them off the walls, floor and
+/// ceiling, or landing those that flew in gently.
+pub fn enforce_boundary_system(
+    commands: ParallelCommands,
+    mut moth_query: Query<LandingMoth, Flying>,
+    bounds: Res<RoomBounds>,
+    moth_config: Res<MothConfig>,
+) {
+    moth_query.par_iter_mut().for_each(
+        |(moth, mut transform, mut velocity, mut state, mut rng)| {
+            let pos = &mut transform.translation;
+
+            // Enforce cylindrical boundary
+            let mut wall_normal = None;
+            let horizontal_pos = Vec2::new(pos.x, pos.z);
+            if horizontal_pos.length() > bounds.radius {
+                wall_normal = Some(horizontal_pos.normalize() * -1.0);
+                let clamped = horizontal_pos.clamp_length_max(bounds.radius);
+                pos.x = clamped.x;
+                pos.z = clamped.y;
             }
-            pos.x = pos.x.clamp(-ROOM_RADIUS, ROOM_RADIUS);
-            pos.z = pos.z.clamp(-ROOM_RADIUS, ROOM_RADIUS);
-        }
 
-        // Enforce vertical boundary
-        if pos.y > CEILING_HEIGHT {
-            pos.y = CEILING_HEIGHT;
-            velocity.0.y = -velocity.0.y.abs();
-        } else if pos.y < FLOOR_HEIGHT {
-            pos.y = FLOOR_HEIGHT;
-            velocity.0.y = velocity.0.y.abs();
-        }
-    }
+            // Enforce vertical boundary
+            let mut vertical_normal = None;
+            if pos.y > bounds.ceiling {
+                pos.y = bounds.ceiling;
+                vertical_normal = Some(-1.0);
+            } else if pos.y < bounds.floor {
+                pos.y = bounds.floor;
+                vertical_normal = Some(1.0);
+            }
+
+            // In a corner the moth lands on the floor or ceiling
+            let surface_normal = vertical_normal
+                .map(|y| Vec3::new(0.0, y, 0.0))
+                .or(wall_normal.map(|normal| Vec3::new(normal.x, 0.0, normal.y)));
+            if let Some(normal) = surface_normal
+                && let Some(perch) = perch_on(velocity.0, normal, &moth_config, &mut rng)
+            {
+                land(
+                    moth,
+                    perch,
+                    &mut transform,
+                    &mut velocity,
+                    &mut state,
+                    &commands,
+                );
+                return;
+            }
+
+            if let Some(normal) = wall_normal {
+                let horizontal_vel = Vec2::new(velocity.0.x, velocity.0.z);
+                if horizontal_vel.dot(normal) < 0.0 {
+                    let reflect = horizontal_vel - 2.0 * horizontal_vel.dot(normal) * normal;
+                    velocity.0.x = reflect.x;
+                    velocity.0.z = reflect.y;
+                }
+            }
+            if let Some(y) = vertical_normal {
+                velocity.0.y = velocity.0.y.abs() * y;
+            }
+        },
+    );
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn camera_control_system(
     mut camera_query: Query<(&mut Transform, &mut OrbitCamera), With<Camera>>,
     keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    touches: Res<Touches>,
     mut touch_events: EventReader<TouchInput>,
     mut touch_state: Local<TouchState>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
+    config: Res<CameraConfig>,
+    interaction: Res<LanternInteraction>,
+    lantern_query: Query<(), With<Lantern>>,
+    ui_query: Query<&Interaction>,
 ) {
     if let Ok((mut transform, mut orbit_camera)) = camera_query.single_mut() {
         let mut angle_delta = 0.0;
+        let mut pitch_delta = 0.0;
+        let mut zoom_factor = 1.0;
+        let dragging_lantern = interaction
+            .dragging
+            .iter()
+            .any(|entity| lantern_query.contains(*entity));
+        // Dragging the replay scrubber or holding a button shouldn't orbit
+        let pressing_ui = ui_query
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed);
+
+        if keys.just_pressed(KeyCode::KeyF) {
+            orbit_camera.mode = match orbit_camera.mode {
+                CameraMode::Orbit => CameraMode::FreeFly,
+                CameraMode::FreeFly => CameraMode::Orbit,
+            };
+        }
 
         // Keyboard controls
-        let keyboard_speed = 1.5;
+        let keyboard_speed = config.keyboard_speed * time.delta_secs();
         if keys.pressed(KeyCode::ArrowLeft) {
-            angle_delta -= keyboard_speed * time.delta_secs();
+            angle_delta -= keyboard_speed;
         }
         if keys.pressed(KeyCode::ArrowRight) {
-            angle_delta += keyboard_speed * time.delta_secs();
+            angle_delta += keyboard_speed;
         }
+        if keys.pressed(KeyCode::ArrowUp) {
+            pitch_delta += keyboard_speed;
+        }
+        if keys.pressed(KeyCode::ArrowDown) {
+            pitch_delta -= keyboard_speed;
+        }
+
+        // Mouse controls
+        if mouse_buttons.any_pressed([MouseButton::Left, MouseButton::Right])
+            && !dragging_lantern
+            && !pressing_ui
+        {
+            angle_delta += mouse_motion.delta.x * config.mouse_sensitivity;
+            pitch_delta += mouse_motion.delta.y * config.mouse_sensitivity;
+        }
+        let scroll_lines = match mouse_scroll.unit {
+            MouseScrollUnit::Line => mouse_scroll.delta.y,
+            MouseScrollUnit::Pixel => mouse_scroll.delta.y / 100.0,
+        };
+        zoom_factor *= 1.0 - scroll_lines * config.zoom_speed;
+
+        // Touch controls: one finger orbits, two fingers pinch to zoom
+        let mut active_touches = touches.iter();
+        if let (Some(a), Some(b)) = (active_touches.next(), active_touches.next()) {
+            let previous = a.previous_position().distance(b.previous_position());
+            let current = a.position().distance(b.position());
+            if current > f32::EPSILON {
+                zoom_factor *= previous / current;
+            }
+        }
+        let pinching = touches.iter().count() >= 2;
 
-        // Touch controls
-        let touch_speed = 0.1;
         for ev in touch_events.read() {
             match ev.phase {
                 TouchPhase::Started => {
@@ -67,9 +157,13 @@ pub fn camera_control_system(
                     touch_state.last_pos = Some(ev.position);
                 }
                 TouchPhase::Moved => {
-                    if let Some(last_pos) = touch_state.last_pos {
+                    if let Some(last_pos) = touch_state.last_pos
+                        && !dragging_lantern
+                        && !pinching
+                    {
                         let delta = ev.position - last_pos;
-                        angle_delta += delta.x * touch_speed * time.delta_secs();
+                        angle_delta += delta.x * config.touch_speed * time.delta_secs();
+                        pitch_delta += delta.y * config.touch_speed * time.delta_secs();
                     }
                     touch_state.last_pos = Some(ev.position);
                 }
@@ -80,16 +174,49 @@ pub fn camera_control_system(
             }
         }
 
-        if angle_delta.abs() > f32::EPSILON {
-            orbit_camera.angle += angle_delta;
+        let mode = orbit_camera.mode;
+        let goal = &mut orbit_camera.goal;
+        let eye = goal.eye();
+        goal.angle += angle_delta;
+        goal.pitch = (goal.pitch + pitch_delta).clamp(config.min_pitch, config.max_pitch);
+        goal.radius = (goal.radius * zoom_factor).clamp(config.min_radius, config.max_radius);
 
-            let new_x = orbit_camera.angle.cos() * orbit_camera.radius;
-            let new_z = orbit_camera.angle.sin() * orbit_camera.radius;
+        // WASD pans the focus in orbit mode and flies the camera in free-fly
+        // mode; Q and E move straight down and up.
+        let forward = (goal.focus - eye).with_y(0.0).normalize_or_zero();
+        let right = forward.cross(Vec3::Y);
+        let mut movement = Vec3::ZERO;
+        if keys.pressed(KeyCode::KeyW) {
+            movement += forward;
+        }
+        if keys.pressed(KeyCode::KeyS) {
+            movement -= forward;
+        }
+        if keys.pressed(KeyCode::KeyD) {
+            movement += right;
+        }
+        if keys.pressed(KeyCode::KeyA) {
+            movement -= right;
+        }
+        if keys.pressed(KeyCode::KeyE) {
+            movement += Vec3::Y;
+        }
+        if keys.pressed(KeyCode::KeyQ) {
+            movement -= Vec3::Y;
+        }
+        let step = movement.normalize_or_zero() * config.fly_speed * time.delta_secs();
 
-            transform.translation.x = new_x;
-            transform.translation.z = new_z;
+        goal.focus = match mode {
+            CameraMode::Orbit => goal.focus + step,
+            // Turn in place: swing the focus around the eye rather than the
+            // eye around the focus
+            CameraMode::FreeFly => eye + step - (goal.eye() - goal.focus),
+        };
 
-            transform.look_at(Vec3::new(0.0, 5.0, 0.0), Vec3::Y);
-        }
+        let t = 1.0 - (-config.damping * time.delta_secs()).exp();
+        orbit_camera.rig = orbit_camera.rig.lerp(&orbit_camera.goal, t);
+
+        transform.translation = orbit_camera.rig.eye();
+        transform.look_at(orbit_camera.rig.focus, Vec3::Y);
     }
 }
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::components::{Lantern, LanternBob, OrbitCamera, ShareButton};
+use crate::config::{LanternLayout, MothConfig};
+use crate::resources::SimulationSeed;
+use crate::setup::{BUTTON_COLOUR, BUTTON_HOVER_COLOUR};
+use crate::share::ShareSettings;
+
+/// The settings the web build was opened with, read from the page's query
+/// string and hash. Keys in the hash win.
+#[cfg(target_arch = "wasm32")]
+pub fn page_share_settings() -> ShareSettings {
+    let Some(location) = web_sys::window().map(|window| window.location()) else {
+        return ShareSettings::default();
+    };
+    let search = location.search().unwrap_or_default();
+    let hash = location.hash().unwrap_or_default();
+    let query = format!(
+        "{}&{}",
+        search.trim_start_matches('?'),
+        hash.trim_start_matches('#')
+    );
+    ShareSettings::from_query(&query)
+}
+
+/// Puts a link to `query` on the clipboard and in the address bar. Natively
+/// there's no page to link to, so the query is logged instead.
+#[cfg(target_arch = "wasm32")]
+fn publish_share_link(query: &str) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    let url = format!(
+        "{}{}?{query}",
+        location.origin().unwrap_or_default(),
+        location.pathname().unwrap_or_default()
+    );
+
+    // The promise settles on its own; a refused clipboard still leaves the
+    // link in the address bar
+    let _ = window.navigator().clipboard().write_text(&url);
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+    }
+    info!("Copied share link {url}");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn publish_share_link(query: &str) {
+    info!("Share link query: ?{query}");
+}
+
+type ShareButtonChanged = (With<ShareButton>, Changed<Interaction>);
+
+/// `U` or the share button copies a link that recreates the current setup:
+/// the seed, moth count and attraction, the lanterns where they hang now and
+/// the camera.
+pub fn share_link_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), ShareButtonChanged>,
+    seed: Res<SimulationSeed>,
+    moth_config: Res<MothConfig>,
+    lantern_query: Query<(&Transform, &LanternBob), With<Lantern>>,
+    camera_query: Query<&OrbitCamera>,
+) {
+    let mut pressed = keys.just_pressed(KeyCode::KeyU);
+    for (interaction, mut background) in button_query.iter_mut() {
+        match interaction {
+            Interaction::Pressed => pressed = true,
+            Interaction::Hovered => background.0 = BUTTON_HOVER_COLOUR,
+            Interaction::None => background.0 = BUTTON_COLOUR,
+        }
+    }
+    if !pressed {
+        return;
+    }
+
+    let lanterns = lantern_query
+        .iter()
+        .map(|(transform, bob)| transform.translation.with_y(bob.initial_y).into())
+        .collect();
+    let settings = ShareSettings {
+        seed: Some(seed.0),
+        moth_count: Some(moth_config.moth_count),
+        attraction_factor: Some(moth_config.attraction_factor),
+        layout: Some(LanternLayout::Positions(lanterns)),
+        camera: camera_query.single().ok().map(|camera| camera.goal),
+    };
+    publish_share_link(&settings.to_query());
+}
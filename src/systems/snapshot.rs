@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::config::SnapshotConfig;
+use crate::resources::SnapshotRequests;
+use crate::snapshot::{restore_snapshot, save_snapshot};
+use crate::systems::{request_text_file, save_text_file};
+
+/// `K` saves a snapshot and `L` restores one. In the browser these download
+/// the snapshot and open a file picker.
+pub fn snapshot_keys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut requests: ResMut<SnapshotRequests>,
+    config: Res<SnapshotConfig>,
+) {
+    if keys.just_pressed(KeyCode::KeyK) {
+        requests.save = true;
+    }
+    if keys.just_pressed(KeyCode::KeyL) {
+        request_text_file(&config.path, &requests.load);
+    }
+}
+
+pub fn snapshot_system(world: &mut World) {
+    let (save, load) = {
+        let mut requests = world.resource_mut::<SnapshotRequests>();
+        let load = requests.load.lock().unwrap().take();
+        (std::mem::take(&mut requests.save), load)
+    };
+
+    if save {
+        match save_snapshot(world) {
+            Ok(text) => save_text_file(&world.resource::<SnapshotConfig>().path, &text),
+            Err(err) => warn!("Failed to save snapshot: {err}"),
+        }
+    }
+
+    if let Some(text) = load {
+        match restore_snapshot(world, &text) {
+            Ok(()) => info!("Restored snapshot"),
+            Err(err) => warn!("Failed to restore snapshot: {err}"),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+
+use crate::components::{TimeControlButton, TimeControlLabel};
+use crate::resources::TimeControl;
+use crate::setup::{BUTTON_COLOUR, BUTTON_HOVER_COLOUR};
+
+pub const SIMULATION_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// Run condition for systems that should only run while simulated time moves,
+/// so a paused scene doesn't keep rolling dice or nudging velocities.
+pub fn simulation_advancing(time: Res<Time>) -> bool {
+    !time.delta().is_zero()
+}
+
+fn apply_time_control(
+    button: TimeControlButton,
+    virtual_time: &mut Time<Virtual>,
+    control: &mut TimeControl,
+) {
+    match button {
+        TimeControlButton::TogglePause => {
+            if virtual_time.is_paused() {
+                virtual_time.unpause();
+            } else {
+                virtual_time.pause();
+            }
+        }
+        TimeControlButton::Step => {
+            virtual_time.pause();
+            control.step_requested = true;
+        }
+        TimeControlButton::Slower => {
+            let current = virtual_time.relative_speed_f64();
+            if let Some(&speed) = SIMULATION_SPEEDS.iter().rev().find(|&&s| s < current) {
+                virtual_time.set_relative_speed_f64(speed);
+            }
+        }
+        TimeControlButton::Faster => {
+            let current = virtual_time.relative_speed_f64();
+            if let Some(&speed) = SIMULATION_SPEEDS.iter().find(|&&s| s > current) {
+                virtual_time.set_relative_speed_f64(speed);
+            }
+        }
+    }
+}
+
+/// `Space` pauses, `.` steps one fixed tick and `[`/`]` halve or double the
+/// speed.
+pub fn time_control_keys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut control: ResMut<TimeControl>,
+) {
+    for (key, button) in [
+        (KeyCode::Space, TimeControlButton::TogglePause),
+        (KeyCode::Period, TimeControlButton::Step),
+        (KeyCode::BracketLeft, TimeControlButton::Slower),
+        (KeyCode::BracketRight, TimeControlButton::Faster),
+    ] {
+        if keys.just_pressed(key) {
+            apply_time_control(button, &mut virtual_time, &mut control);
+        }
+    }
+}
+
+pub fn time_control_button_system(
+    mut button_query: Query<
+        (&Interaction, &TimeControlButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut control: ResMut<TimeControl>,
+) {
+    for (interaction, button, mut background) in button_query.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                apply_time_control(*button, &mut virtual_time, &mut control);
+            }
+            Interaction::Hovered => background.0 = BUTTON_HOVER_COLOUR,
+            Interaction::None => background.0 = BUTTON_COLOUR,
+        }
+    }
+}
+
+/// Runs in `First` after Bevy updates its clocks. A paused virtual clock is
+/// advanced by one fixed timestep and copied into `Time`, so every `Update`
+/// system sees the step as an ordinary frame.
+pub fn single_step_system(
+    mut control: ResMut<TimeControl>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    if !std::mem::take(&mut control.step_requested) || !virtual_time.is_paused() {
+        return;
+    }
+    virtual_time.advance_by(fixed_time.timestep());
+    *time = virtual_time.as_generic();
+}
+
+pub fn time_control_label_system(
+    virtual_time: Res<Time<Virtual>>,
+    mut label_query: Query<&mut Text, With<TimeControlLabel>>,
+) {
+    let label = if virtual_time.is_paused() {
+        format!("Paused ({}x)", virtual_time.relative_speed_f64())
+    } else {
+        format!("{}x", virtual_time.relative_speed_f64())
+    };
+    for mut text in label_query.iter_mut() {
+        if text.0 != label {
+            text.0 = label.clone();
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use crate::components::{Lantern, Moth, Trail, TrailPoint, Velocity};
+use crate::config::{MothConfig, TrailColouring, TrailConfig};
+use crate::resources::{MothSelection, SpatialIndex};
+
+/// `T` toggles trails, `Shift+T` limits them to the selected moth and `C`
+/// switches the colouring.
+pub fn trail_toggle_system(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<TrailConfig>) {
+    if keys.just_pressed(KeyCode::KeyT) {
+        if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            config.selected_only = !config.selected_only;
+        } else {
+            config.enabled = !config.enabled;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::KeyC) {
+        config.colouring = match config.colouring {
+            TrailColouring::Speed => TrailColouring::NearestLantern,
+            TrailColouring::NearestLantern => TrailColouring::Speed,
+        };
+    }
+}
+
+pub fn record_trail_system(
+    mut moth_query: Query<(&Transform, &Velocity, &mut Trail), With<Moth>>,
+    lantern_query: Query<(Entity, &Transform), With<Lantern>>,
+    spatial_index: Res<SpatialIndex>,
+    config: Res<TrailConfig>,
+    time: Res<Time>,
+    mut since_last_sample: Local<f32>,
+) {
+    if !config.enabled {
+        // Drop old points so re-enabling doesn't join them to new ones
+        if config.is_changed() {
+            for (_, _, mut trail) in moth_query.iter_mut() {
+                trail.points.clear();
+            }
+        }
+        return;
+    }
+
+    *since_last_sample += time.delta_secs();
+    if *since_last_sample < config.sample_interval {
+        return;
+    }
+    *since_last_sample = 0.0;
+
+    for (transform, velocity, mut trail) in moth_query.iter_mut() {
+        let position = transform.translation;
+        let nearest_lantern = spatial_index
+            .get_nearby(position.xz())
+            .into_iter()
+            .filter_map(|entity| lantern_query.get(entity).ok())
+            .min_by(|(_, a), (_, b)| {
+                a.translation
+                    .distance_squared(position)
+                    .total_cmp(&b.translation.distance_squared(position))
+            })
+            .map(|(entity, _)| entity);
+
+        trail.points.push_back(TrailPoint {
+            position,
+            speed: velocity.0.length(),
+            nearest_lantern,
+        });
+        while trail.points.len() > config.length {
+            trail.points.pop_front();
+        }
+    }
+}
+
+fn trail_colour(point: &TrailPoint, colouring: TrailColouring, moth_speed: f32) -> Color {
+    match colouring {
+        TrailColouring::Speed => {
+            let t = (point.speed / moth_speed).clamp(0.0, 1.0);
+            let slow = Vec3::new(0.2, 0.4, 1.0);
+            let fast = Vec3::new(1.0, 0.8, 0.2);
+            let rgb = slow.lerp(fast, t);
+            Color::srgb(rgb.x, rgb.y, rgb.z)
+        }
+        // Spread hues by the golden angle so neighbouring lanterns differ
+        TrailColouring::NearestLantern => match point.nearest_lantern {
+            Some(lantern) => Color::hsl((lantern.index() as f32 * 137.5) % 360.0, 0.8, 0.6),
+            None => Color::srgb(0.5, 0.5, 0.5),
+        },
+    }
+}
+
+pub fn draw_trails_system(
+    mut gizmos: Gizmos,
+    moth_query: Query<(Entity, &Trail), With<Moth>>,
+    config: Res<TrailConfig>,
+    moth_config: Res<MothConfig>,
+    selection: Res<MothSelection>,
+) {
+    if !config.enabled {
+        return;
+    }
+
+    for (entity, trail) in moth_query.iter() {
+        if config.selected_only && selection.selected != Some(entity) {
+            continue;
+        }
+
+        let len = trail.points.len() as f32;
+        gizmos.linestrip_gradient(trail.points.iter().enumerate().map(|(i, point)| {
+            let fade = (i + 1) as f32 / len;
+            let colour = trail_colour(point, config.colouring, moth_config.moth_speed);
+            (point.position, colour.with_alpha(fade))
+        }));
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::components::{LanternId, LightTrap, Moth};
+use crate::config::TrapConfig;
+use crate::events::MothCaptured;
+use crate::systems::save_text_file;
+
+/// Despawns moths that have flown into a trap's bucket.
+pub fn trap_capture_system(
+    mut commands: Commands,
+    moth_query: Query<(Entity, &Transform), With<Moth>>,
+    mut trap_query: Query<(Entity, &Transform, &mut LightTrap)>,
+    mut captured: EventWriter<MothCaptured>,
+) {
+    if trap_query.is_empty() {
+        return;
+    }
+
+    for (moth, moth_transform) in &moth_query {
+        let trap = trap_query.iter_mut().find(|(_, transform, trap)| {
+            trap.contains(transform.translation, moth_transform.translation)
+        });
+        if let Some((entity, _, mut trap)) = trap {
+            trap.catches += 1;
+            commands.entity(moth).despawn();
+            captured.write(MothCaptured { moth, trap: entity });
+        }
+    }
+}
+
+/// Adds a point to every trap's catch curve each `TrapConfig::sample_interval`.
+pub fn trap_sample_system(
+    mut since_sample: Local<f32>,
+    time: Res<Time>,
+    config: Res<TrapConfig>,
+    mut trap_query: Query<&mut LightTrap>,
+) {
+    *since_sample += time.delta_secs();
+    if *since_sample < config.sample_interval {
+        return;
+    }
+    *since_sample -= config.sample_interval;
+
+    let now = time.elapsed_secs();
+    for mut trap in &mut trap_query {
+        let catches = trap.catches;
+        trap.catch_curve.push((now, catches));
+    }
+}
+
+/// One row per point on each trap's catch curve, with a header. Traps are
+/// identified by the same ids as the JavaScript and Python APIs use.
+pub fn catch_curves_csv<'a>(traps: impl IntoIterator<Item = (u32, Vec3, &'a LightTrap)>) -> String {
+    let mut csv = String::from("trap,x,y,z,time,catches\n");
+    for (id, position, trap) in traps {
+        for (time, catches) in &trap.catch_curve {
+            csv.push_str(&format!(
+                "{id},{},{},{},{time},{catches}\n",
+                position.x, position.y, position.z
+            ));
+        }
+    }
+    csv
+}
+
+/// `X` exports every trap's catch curve as CSV, downloaded in the browser.
+pub fn trap_keys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<TrapConfig>,
+    trap_query: Query<(&LanternId, &Transform, &LightTrap)>,
+) {
+    if !keys.just_pressed(KeyCode::KeyX) {
+        return;
+    }
+    if trap_query.is_empty() {
+        info!("There are no light traps to export");
+        return;
+    }
+
+    let traps = trap_query
+        .iter()
+        .map(|(id, transform, trap)| (id.0, transform.translation, trap));
+    save_text_file(&config.export_path, &catch_curves_csv(traps));
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use bevy::scene::ron;
+use serde::Deserialize;
+
+use crate::api::{
+    ApiCommand, ApiPlugin, ApiQueue, api_command_system, set_config_field, update_config_field,
+};
+use crate::components::{CameraRig, OrbitCamera};
+use crate::config::{LanternConfig, MothConfig};
+use crate::systems::{replaying, simulation_advancing};
+
+/// Things to do at set points in a run, read from a RON file like
+///
+/// ```ron
+/// (
+///     events: [
+///         (at: 30.0, action: SetLantern(lantern: 3, on: true)),
+///         (at: 60.0, action: ScaleConfig(field: "moth.moth_speed", factor: 2.0)),
+///         (at: 90.0, action: SpawnMoths(count: 200, position: (0.0, 3.0, 0.0))),
+///     ],
+/// )
+/// ```
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Timeline {
+    pub events: Vec<TimelineEvent>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TimelineEvent {
+    /// Simulated seconds since the run started.
+    pub at: f32,
+    pub action: TimelineAction,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum TimelineAction {
+    /// Switches a lantern, named by its `LanternId`, on or off. Lanterns are
+    /// numbered from zero in the order they were spawned.
+    SetLantern {
+        lantern: u32,
+        on: bool,
+    },
+    ToggleLantern {
+        lantern: u32,
+    },
+    /// Sets a numeric `moth.*` or `lantern.*` config field.
+    SetConfig {
+        field: String,
+        value: f64,
+    },
+    /// Multiplies a numeric config field.
+    ScaleConfig {
+        field: String,
+        factor: f64,
+    },
+    SpawnMoths {
+        count: usize,
+        position: Vec3,
+    },
+    SetSpeed(f64),
+    Pause,
+    /// Eases the camera to look at `focus` from `radius` away. Does nothing
+    /// without a window.
+    Camera {
+        focus: Vec3,
+        radius: f32,
+        angle: f32,
+        pitch: f32,
+    },
+}
+
+impl Timeline {
+    pub fn from_ron(text: &str) -> Result<Self> {
+        Ok(ron::de::from_str(text)?)
+    }
+}
+
+/// The timeline a run follows and how far through it the run has got.
+#[derive(Resource, Default)]
+pub struct TimelinePlayer {
+    events: Vec<TimelineEvent>,
+    next: usize,
+}
+
+impl TimelinePlayer {
+    pub fn new(timeline: Timeline) -> Self {
+        let mut events = timeline.events;
+        events.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Self { events, next: 0 }
+    }
+
+    /// Whether every event has happened.
+    pub fn finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+}
+
+/// Plays the `TimelinePlayer` resource's timeline, which starts out empty.
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ApiPlugin>() {
+            app.add_plugins(ApiPlugin);
+        }
+        app.init_resource::<TimelinePlayer>().add_systems(
+            Update,
+            timeline_system
+                .before(api_command_system)
+                .run_if(simulation_advancing)
+                .run_if(not(replaying)),
+        );
+    }
+}
+
+pub fn timeline_system(
+    mut player: ResMut<TimelinePlayer>,
+    time: Res<Time<Virtual>>,
+    queue: Res<ApiQueue>,
+    mut moth_config: ResMut<MothConfig>,
+    mut lantern_config: ResMut<LanternConfig>,
+    mut camera_query: Query<&mut OrbitCamera>,
+) {
+    let now = time.elapsed_secs();
+
+    while let Some(event) = player.events.get(player.next)
+        && event.at <= now
+    {
+        let action = event.action.clone();
+        player.next += 1;
+        match action {
+            TimelineAction::SetLantern { lantern, on } => queue.push(ApiCommand::SetLantern {
+                id: lantern,
+                on: Some(on),
+            }),
+            TimelineAction::ToggleLantern { lantern } => {
+                queue.push(ApiCommand::SetLantern {
+                    id: lantern,
+                    on: None,
+                });
+            }
+            TimelineAction::SetConfig { field, value } => {
+                if let Err(err) =
+                    set_config_field(&field, value, &mut moth_config, &mut lantern_config)
+                {
+                    warn!("Skipping timeline event: {err}");
+                }
+            }
+            TimelineAction::ScaleConfig { field, factor } => {
+                if let Err(err) = update_config_field(
+                    &field,
+                    |current| current * factor,
+                    &mut moth_config,
+                    &mut lantern_config,
+                ) {
+                    warn!("Skipping timeline event: {err}");
+                }
+            }
+            TimelineAction::SpawnMoths { count, position } => {
+                queue.push(ApiCommand::SpawnMoths { count, position });
+            }
+            TimelineAction::SetSpeed(speed) => queue.push(ApiCommand::SetSpeed(speed)),
+            TimelineAction::Pause => queue.push(ApiCommand::SetPaused(true)),
+            TimelineAction::Camera {
+                focus,
+                radius,
+                angle,
+                pitch,
+            } => {
+                for mut camera in &mut camera_query {
+                    camera.goal = CameraRig {
+                        focus,
+                        radius,
+                        angle,
+                        pitch,
+                    };
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! The JavaScript API of the web build. Trunk exposes these functions on
+//! `window.wasmBindings` once the module has loaded.
+
+use std::cell::RefCell;
+use std::sync::LazyLock;
+
+use bevy::prelude::*;
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::api::{ApiCommand, ApiMetrics, ApiPlugin, ApiQueue, api_metrics_system};
+use crate::components::LanternId;
+use crate::events::{LanternFlickered, LanternTurnedOff, LanternTurnedOn};
+use crate::simulation::MothUpdate;
+
+static QUEUE: LazyLock<ApiQueue> = LazyLock::new(ApiQueue::default);
+
+thread_local! {
+    static METRICS_CALLBACK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    static LANTERN_CALLBACK: RefCell<Option<Function>> = const { RefCell::new(None) };
+}
+
+#[wasm_bindgen]
+pub fn pause() {
+    QUEUE.push(ApiCommand::SetPaused(true));
+}
+
+#[wasm_bindgen]
+pub fn resume() {
+    QUEUE.push(ApiCommand::SetPaused(false));
+}
+
+/// Sets how fast simulated time runs relative to real time.
+#[wasm_bindgen(js_name = setSpeed)]
+pub fn set_speed(speed: f64) {
+    QUEUE.push(ApiCommand::SetSpeed(speed));
+}
+
+/// Sets a numeric config field, such as `moth.attraction_factor`,
+/// `moth.view_radius` or `lantern.on_chance`.
+#[wasm_bindgen(js_name = setConfig)]
+pub fn set_config(field: &str, value: f64) {
+    QUEUE.push(ApiCommand::SetConfig {
+        field: field.to_owned(),
+        value,
+    });
+}
+
+/// Switches a lantern, by the id reported in metrics, on or off.
+#[wasm_bindgen(js_name = setLantern)]
+pub fn set_lantern(id: u32, on: bool) {
+    QUEUE.push(ApiCommand::SetLantern { id, on: Some(on) });
+}
+
+#[wasm_bindgen(js_name = toggleLantern)]
+pub fn toggle_lantern(id: u32) {
+    QUEUE.push(ApiCommand::SetLantern { id, on: None });
+}
+
+/// Releases `count` moths at a point in the room, flying off in random
+/// directions.
+#[wasm_bindgen(js_name = spawnMoths)]
+pub fn spawn_moths(count: u32, x: f32, y: f32, z: f32) {
+    QUEUE.push(ApiCommand::SpawnMoths {
+        count: count as usize,
+        position: Vec3::new(x, y, z),
+    });
+}
+
+/// Calls `callback` with a metrics object a couple of times a second. Pass
+/// `null` to stop.
+#[wasm_bindgen(js_name = onMetrics)]
+pub fn on_metrics(callback: Option<Function>) {
+    METRICS_CALLBACK.with_borrow_mut(|stored| *stored = callback);
+}
+
+/// Calls `callback` with an object for every lantern turning on, off or
+/// flickering. Pass `null` to stop.
+#[wasm_bindgen(js_name = onLanternEvent)]
+pub fn on_lantern_event(callback: Option<Function>) {
+    LANTERN_CALLBACK.with_borrow_mut(|stored| *stored = callback);
+}
+
+/// Connects the app to the functions above.
+pub struct WebApiPlugin;
+
+impl Plugin for WebApiPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ApiPlugin>() {
+            app.add_plugins(ApiPlugin);
+        }
+        app.insert_resource(QUEUE.clone()).add_systems(
+            Update,
+            (
+                publish_metrics_system.after(api_metrics_system),
+                publish_lantern_events_system.after(MothUpdate),
+            ),
+        );
+    }
+}
+
+fn object(entries: &[(&str, JsValue)]) -> JsValue {
+    let object = Object::new();
+    for (key, value) in entries {
+        let _ = Reflect::set(&object, &JsValue::from_str(key), value);
+    }
+    object.into()
+}
+
+fn call(callback: &'static std::thread::LocalKey<RefCell<Option<Function>>>, value: JsValue) {
+    callback.with_borrow(|callback| {
+        if let Some(callback) = callback
+            && let Err(err) = callback.call1(&JsValue::NULL, &value)
+        {
+            warn!("JavaScript callback failed: {err:?}");
+        }
+    });
+}
+
+fn publish_metrics_system(mut metrics: EventReader<ApiMetrics>) {
+    for metrics in metrics.read() {
+        let lanterns: Array = metrics
+            .lanterns
+            .iter()
+            .map(|lantern| {
+                object(&[
+                    ("id", lantern.id.into()),
+                    ("x", lantern.position.x.into()),
+                    ("y", lantern.position.y.into()),
+                    ("z", lantern.position.z.into()),
+                    ("on", lantern.is_on.into()),
+                    ("intensity", lantern.intensity.into()),
+                ])
+            })
+            .collect();
+        call(
+            &METRICS_CALLBACK,
+            object(&[
+                ("time", metrics.time.into()),
+                ("paused", metrics.paused.into()),
+                ("speed", metrics.speed.into()),
+                ("moths", (metrics.moths as u32).into()),
+                ("attracted", metrics.attracted.into()),
+                ("lanterns", lanterns.into()),
+            ]),
+        );
+    }
+}
+
+fn publish_lantern_events_system(
+    mut turned_on: EventReader<LanternTurnedOn>,
+    mut turned_off: EventReader<LanternTurnedOff>,
+    mut flickered: EventReader<LanternFlickered>,
+    id_query: Query<&LanternId>,
+) {
+    let events = turned_on
+        .read()
+        .map(|ev| ("on", ev.entity, ev.radiance, ev.intensity))
+        .chain(
+            turned_off
+                .read()
+                .map(|ev| ("off", ev.entity, ev.radiance, ev.intensity)),
+        )
+        .chain(
+            flickered
+                .read()
+                .map(|ev| ("flicker", ev.entity, ev.radiance, ev.intensity)),
+        );
+    for (kind, entity, radiance, intensity) in events {
+        // A lantern deleted in the same frame has nothing left to name it by
+        let Ok(id) = id_query.get(entity) else {
+            continue;
+        };
+        call(
+            &LANTERN_CALLBACK,
+            object(&[
+                ("type", kind.into()),
+                ("lantern", id.0.into()),
+                ("radiance", radiance.into()),
+                ("intensity", intensity.into()),
+            ]),
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::asset::{RenderAssetUsages, load_internal_asset, weak_handle};
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+
+use crate::simulation::MothUpdate;
+use crate::systems::wing_flap_system;
+
+const MOTH_SHADER_HANDLE: Handle<Shader> = weak_handle!("5f0c7a3e-2b1d-4c8e-9a64-1d3e8b7f2c90");
+const MOTH_PREPASS_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("a2d9e4b1-7c35-4f0a-b8e2-6c1f9d40a5e3");
+const MOTH_WINGS_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("c81b5f27-94e6-4d3a-a0f5-3e7d2b9c6a18");
+
+/// Half the width of the body, where the wings are hinged. The vertex shader
+/// swings everything further out than this; keep it in step with `WING_ROOT`
+/// in `moth_wings.wgsl`.
+pub const WING_ROOT: f32 = 0.012;
+
+/// The material every moth is drawn with: a `StandardMaterial` whose vertex
+/// shader flaps the wings.
+pub type MothMaterial = ExtendedMaterial<StandardMaterial, MothWings>;
+
+/// Moves each moth's wings to where it is in its wingbeat. The wingbeat phase
+/// comes from the moth's `MeshTag`, kept up to date by `wing_flap_system`, so
+/// all the moths still share one mesh and material and are drawn as a single
+/// instanced batch. Everything the shader needs is per moth, so the extension
+/// itself has no bindings.
+#[derive(Asset, AsBindGroup, Reflect, Default, Debug, Clone)]
+pub struct MothWings {}
+
+impl MaterialExtension for MothWings {
+    fn vertex_shader() -> ShaderRef {
+        MOTH_SHADER_HANDLE.into()
+    }
+
+    // Shadows are drawn with the prepass shader, so they flap too
+    fn prepass_vertex_shader() -> ShaderRef {
+        MOTH_PREPASS_SHADER_HANDLE.into()
+    }
+}
+
+/// Draws moths with flapping wings. Only needed when rendering; headless apps
+/// give moths the same mesh and material but never draw them.
+pub struct MothWingsPlugin;
+
+impl Plugin for MothWingsPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            MOTH_WINGS_SHADER_HANDLE,
+            "shaders/moth_wings.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            MOTH_SHADER_HANDLE,
+            "shaders/moth.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            MOTH_PREPASS_SHADER_HANDLE,
+            "shaders/moth_prepass.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.add_plugins(MaterialPlugin::<MothMaterial>::default())
+            .register_type::<MothWings>()
+            .add_systems(Update, wing_flap_system.after(MothUpdate));
+    }
+}
+
+/// A low-poly moth facing -Z, the way `Transform::look_to` points it: a
+/// diamond-section body with a flat wing hinged to each side. Flat shaded and
+/// unindexed, at 12 triangles.
+pub fn moth_mesh() -> Mesh {
+    const NOSE: Vec3 = Vec3::new(0.0, 0.0, -0.05);
+    const TAIL: Vec3 = Vec3::new(0.0, 0.0, 0.05);
+    // The widest part of the body, a little behind the head
+    let ring = [
+        Vec3::new(WING_ROOT, 0.0, -0.015),
+        Vec3::new(0.0, WING_ROOT, -0.015),
+        Vec3::new(-WING_ROOT, 0.0, -0.015),
+        Vec3::new(0.0, -WING_ROOT, -0.015),
+    ];
+
+    let mut triangles = Vec::new();
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        triangles.push([NOSE, b, a]);
+        triangles.push([TAIL, a, b]);
+    }
+
+    // The right wing, from the front of its root round to the back. The left
+    // is its mirror image, wound the other way so both face up.
+    let wing = [
+        Vec3::new(WING_ROOT, 0.0, -0.02),
+        Vec3::new(WING_ROOT + 0.07, 0.0, -0.005),
+        Vec3::new(WING_ROOT + 0.045, 0.0, 0.035),
+        Vec3::new(WING_ROOT, 0.0, 0.025),
+    ];
+    for [a, b, c] in [[0, 3, 2], [0, 2, 1]] {
+        triangles.push([wing[a], wing[b], wing[c]]);
+        let mirror = Vec3::new(-1.0, 1.0, 1.0);
+        triangles.push([wing[a] * mirror, wing[c] * mirror, wing[b] * mirror]);
+    }
+
+    let positions: Vec<Vec3> = triangles.into_iter().flatten().collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_computed_flat_normals()
+}
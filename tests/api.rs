@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+
+use phototaxis::api::{ApiCommand, ApiConfig, ApiMetrics, ApiPlugin, ApiQueue};
+use phototaxis::components::{Lantern, LanternId, Moth};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::resources::NextLanternId;
+use phototaxis::simulation::headless_app;
+use phototaxis::snapshot::{restore_snapshot, save_snapshot};
+
+fn app() -> App {
+    let mut app = headless_app(11);
+    app.add_plugins(ApiPlugin)
+        .insert_resource(MothConfig {
+            moth_count: 10,
+            ..default()
+        })
+        .insert_resource(LanternConfig {
+            layout: LanternLayout::Ring {
+                count: 3,
+                radius: 5.0,
+            },
+            on_chance: 0.0,
+            ..default()
+        });
+    app.update();
+    app
+}
+
+fn send(app: &mut App, command: ApiCommand) {
+    app.world().resource::<ApiQueue>().push(command);
+    app.update();
+}
+
+#[test]
+fn config_fields_are_set_by_name() {
+    let mut app = app();
+    send(
+        &mut app,
+        ApiCommand::SetConfig {
+            field: "moth.attraction_factor".into(),
+            value: 2.5,
+        },
+    );
+    send(
+        &mut app,
+        ApiCommand::SetConfig {
+            field: "lantern.on_chance".into(),
+            value: 0.5,
+        },
+    );
+    send(
+        &mut app,
+        ApiCommand::SetConfig {
+            field: "moth.no_such_field".into(),
+            value: 1.0,
+        },
+    );
+
+    assert_eq!(app.world().resource::<MothConfig>().attraction_factor, 2.5);
+    assert_eq!(app.world().resource::<LanternConfig>().on_chance, 0.5);
+}
+
+#[test]
+fn speeds_that_are_not_finite_are_ignored() {
+    let mut app = app();
+    send(&mut app, ApiCommand::SetSpeed(2.0));
+    for speed in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        send(&mut app, ApiCommand::SetSpeed(speed));
+    }
+    assert_eq!(
+        app.world().resource::<Time<Virtual>>().relative_speed_f64(),
+        2.0
+    );
+
+    send(&mut app, ApiCommand::SetSpeed(-1.0));
+    assert_eq!(
+        app.world().resource::<Time<Virtual>>().relative_speed_f64(),
+        0.0
+    );
+}
+
+#[test]
+fn lanterns_toggle_by_id() {
+    let mut app = app();
+    let world = app.world_mut();
+    let (lantern, &LanternId(id)) = world
+        .query::<(Entity, &LanternId)>()
+        .iter(world)
+        .next()
+        .unwrap();
+    let is_on = |app: &App| app.world().get::<Lantern>(lantern).unwrap().is_on;
+
+    send(&mut app, ApiCommand::SetLantern { id, on: None });
+    assert!(is_on(&app));
+    send(&mut app, ApiCommand::SetLantern { id, on: Some(true) });
+    assert!(is_on(&app));
+    send(
+        &mut app,
+        ApiCommand::SetLantern {
+            id,
+            on: Some(false),
+        },
+    );
+    assert!(!is_on(&app));
+}
+
+#[test]
+fn lantern_ids_survive_a_snapshot_restore() {
+    let mut app = app();
+    let snapshot = save_snapshot(app.world_mut()).unwrap();
+    let lantern_ids = |app: &mut App| {
+        let world = app.world_mut();
+        let mut ids: Vec<u32> = world
+            .query::<&LanternId>()
+            .iter(world)
+            .map(|id| id.0)
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+    let ids = lantern_ids(&mut app);
+    assert_eq!(ids, [0, 1, 2]);
+
+    restore_snapshot(app.world_mut(), &snapshot).unwrap();
+    assert_eq!(lantern_ids(&mut app), ids);
+    assert_eq!(app.world().resource::<NextLanternId>().0, 3);
+
+    // The restored lanterns are new entities, but still answer to their ids
+    send(&mut app, ApiCommand::SetLantern { id: 1, on: None });
+    let world = app.world_mut();
+    let lit: Vec<u32> = world
+        .query::<(&LanternId, &Lantern)>()
+        .iter(world)
+        .filter(|(_, lantern)| lantern.is_on)
+        .map(|(id, _)| id.0)
+        .collect();
+    assert_eq!(lit, [1]);
+}
+
+#[test]
+fn spawned_moths_join_the_simulation() {
+    let mut app = app();
+    send(
+        &mut app,
+        ApiCommand::SpawnMoths {
+            count: 5,
+            position: Vec3::new(0.0, 3.0, 0.0),
+        },
+    );
+
+    let world = app.world_mut();
+    assert_eq!(
+        world.query_filtered::<(), With<Moth>>().iter(world).count(),
+        15
+    );
+}
+
+#[test]
+fn metrics_are_reported() {
+    let mut app = app();
+    app.world_mut().resource_mut::<ApiConfig>().metrics_interval = 0.0;
+    app.update();
+
+    let events = app.world().resource::<Events<ApiMetrics>>();
+    let metrics = events.iter_current_update_events().last().unwrap();
+    assert_eq!(metrics.moths, 10);
+    assert_eq!(metrics.lanterns.len(), 3);
+}
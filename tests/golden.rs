@@ -0,0 +1,141 @@
+//! Golden-trajectory regression tests. Each scenario runs a seeded headless
+//! simulation, samples every moth's position and compares the result with a
+//! file under `tests/golden`.
+//!
+//! After an intentional behaviour change, regenerate the files with
+//! `BLESS=1 cargo test --test golden` and commit them.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use phototaxis::components::Moth;
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::simulation::headless_app;
+
+const SEED: u64 = 7;
+const MOTHS: i32 = 24;
+/// Ten seconds of simulated time.
+const TICKS: usize = 600;
+const SAMPLE_EVERY: usize = 30;
+const TOLERANCE: f32 = 1e-3;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.csv"))
+}
+
+/// Runs the scenario and returns `tick,moth,x,y,z` rows with moths numbered
+/// in spawn order.
+fn record(on_chance: f64) -> String {
+    let lanterns = (0..4)
+        .map(|i| {
+            let xz = Vec2::from_angle(i as f32 / 4.0 * std::f32::consts::TAU) * 4.0;
+            Vec3::new(xz.x, 7.0, xz.y).into()
+        })
+        .collect();
+
+    let mut app = headless_app(SEED);
+    app.insert_resource(MothConfig {
+        moth_count: MOTHS,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(lanterns),
+        on_chance,
+        ..default()
+    });
+
+    let mut rows = String::from("tick,moth,x,y,z\n");
+    for tick in 1..=TICKS {
+        app.update();
+        if tick % SAMPLE_EVERY != 0 {
+            continue;
+        }
+
+        let world = app.world_mut();
+        let mut moths: Vec<(Entity, Vec3)> = world
+            .query_filtered::<(Entity, &Transform), With<Moth>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, transform.translation))
+            .collect();
+        moths.sort_by_key(|(entity, _)| *entity);
+
+        for (moth, (_, pos)) in moths.iter().enumerate() {
+            writeln!(rows, "{tick},{moth},{:.4},{:.4},{:.4}", pos.x, pos.y, pos.z).unwrap();
+        }
+    }
+    rows
+}
+
+fn parse(rows: &str) -> Vec<(String, Vec3)> {
+    rows.lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let coord = |i: usize| fields[i].parse::<f32>().unwrap();
+            (
+                format!("tick {} moth {}", fields[0], fields[1]),
+                Vec3::new(coord(2), coord(3), coord(4)),
+            )
+        })
+        .collect()
+}
+
+fn check_golden(name: &str, on_chance: f64) {
+    let path = golden_path(name);
+    let actual = record(on_chance);
+
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing {}, run `BLESS=1 cargo test --test golden` to create it",
+            path.display()
+        )
+    });
+    let (expected, actual) = (parse(&expected), parse(&actual));
+    assert_eq!(
+        expected.len(),
+        actual.len(),
+        "{name}: sample count changed, re-bless if this is intended"
+    );
+
+    let mismatches: Vec<String> = expected
+        .iter()
+        .zip(&actual)
+        .filter(|((_, want), (_, got))| (*want - *got).abs().max_element() > TOLERANCE)
+        .map(|((label, want), (_, got))| format!("{label}: expected {want}, got {got}"))
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "{name}: {} of {} positions drifted beyond {TOLERANCE}, re-bless if this is intended\n{}",
+        mismatches.len(),
+        expected.len(),
+        mismatches
+            .iter()
+            .take(10)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+/// Lanterns never switch on, so only wandering, walls and collisions move
+/// the moths.
+#[test]
+fn wander_trajectories_match_golden() {
+    check_golden("wander", 0.0);
+}
+
+/// Lanterns switch on as soon as their cooldown allows, pulling moths in.
+#[test]
+fn attraction_trajectories_match_golden() {
+    check_golden("attraction", 1.0);
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use phototaxis::components::Lantern;
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::resources::{LanternInteraction, SpatialIndex};
+use phototaxis::simulation::headless_app;
+use phototaxis::systems::lantern_delete_system;
+
+const SEED: u64 = 27;
+
+/// Two lanterns in different index cells and no moths, with lantern deletion
+/// wired up to a keyboard that tests press by hand.
+fn interaction_app() -> App {
+    let mut app = headless_app(SEED);
+    app.insert_resource(MothConfig {
+        moth_count: 0,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(vec![
+            Vec3::new(-5.0, 6.0, -5.0).into(),
+            Vec3::new(5.0, 6.0, 5.0).into(),
+        ]),
+        ..default()
+    })
+    .init_resource::<LanternInteraction>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .add_systems(Update, lantern_delete_system);
+    app.update();
+    app
+}
+
+fn lanterns(app: &mut App) -> Vec<Entity> {
+    let world = app.world_mut();
+    let mut lanterns: Vec<Entity> = world
+        .query_filtered::<Entity, With<Lantern>>()
+        .iter(world)
+        .collect();
+    lanterns.sort();
+    lanterns
+}
+
+#[test]
+fn moved_lanterns_change_cells() {
+    let mut app = interaction_app();
+    let lantern = lanterns(&mut app)[0];
+
+    let destination = Vec3::new(40.0, 6.0, -20.0);
+    app.world_mut()
+        .get_mut::<Transform>(lantern)
+        .unwrap()
+        .translation = destination;
+    app.update();
+
+    let index = app.world().resource::<SpatialIndex>();
+    assert_eq!(
+        index.cell_of(lantern),
+        Some(SpatialIndex::tile(destination.xz()))
+    );
+    let indexed: usize = index.cells().map(|(_, entities)| entities.len()).sum();
+    assert_eq!(indexed, 2, "the old cell still holds the lantern");
+}
+
+#[test]
+fn deleted_lanterns_leave_the_index_and_the_selection() {
+    let mut app = interaction_app();
+    let [deleted, kept] = lanterns(&mut app)[..] else {
+        panic!("expected two lanterns");
+    };
+
+    app.world_mut()
+        .resource_mut::<LanternInteraction>()
+        .selected = Some(deleted);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::Delete);
+    app.update();
+    // The despawn lands at the end of the frame, and the index catches up on
+    // the next one
+    app.update();
+
+    assert!(app.world().get_entity(deleted).is_err());
+    assert_eq!(app.world().resource::<LanternInteraction>().selected, None);
+
+    let index = app.world().resource::<SpatialIndex>();
+    assert_eq!(index.cell_of(deleted), None);
+    assert!(index.cell_of(kept).is_some());
+    assert_eq!(index.cells().count(), 1);
+}
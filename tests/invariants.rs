@@ -0,0 +1,191 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use phototaxis::components::{Lantern, Moth, Velocity};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::resources::{CELL_SIZE, RoomBounds, SpatialIndex};
+use phototaxis::simulation::{headless_app, headless_app_with_timestep};
+
+const SEED: u64 = 42;
+/// One minute of simulated time.
+const TICKS: usize = 60 * 60;
+const EPSILON: f32 = 1e-4;
+
+/// A ring of lanterns well inside the room, switching often enough that
+/// several on/off cycles happen within `TICKS`.
+fn invariant_app() -> App {
+    let lanterns = (0..6)
+        .map(|i| {
+            let xz = Vec2::from_angle(i as f32 / 6.0 * std::f32::consts::TAU) * 5.0;
+            Vec3::new(xz.x, 6.0, xz.y).into()
+        })
+        .collect();
+
+    let mut app = headless_app(SEED);
+    app.insert_resource(MothConfig {
+        moth_count: 200,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(lanterns),
+        on_chance: 0.2,
+        ..default()
+    });
+    app
+}
+
+/// Runs `TICKS` updates, calling `check` with the world after each one.
+fn run(mut check: impl FnMut(&mut World)) {
+    let mut app = invariant_app();
+    for _ in 0..TICKS {
+        app.update();
+        check(app.world_mut());
+    }
+}
+
+#[test]
+fn moths_stay_inside_room_bounds() {
+    run(|world| {
+        let bounds = *world.resource::<RoomBounds>();
+        for transform in world.query_filtered::<&Transform, With<Moth>>().iter(world) {
+            let pos = transform.translation;
+            assert!(
+                pos.xz().length() <= bounds.radius + EPSILON,
+                "moth left the room horizontally at {pos}"
+            );
+            assert!(
+                (bounds.floor - EPSILON..=bounds.ceiling + EPSILON).contains(&pos.y),
+                "moth left the room vertically at {pos}"
+            );
+        }
+    });
+}
+
+#[test]
+fn moths_never_end_a_tick_inside_a_lantern() {
+    run(|world| {
+        let physical_radius = world.resource::<LanternConfig>().physical_radius;
+        let lanterns: Vec<Vec3> = world
+            .query_filtered::<&Transform, With<Lantern>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+
+        for transform in world.query_filtered::<&Transform, With<Moth>>().iter(world) {
+            for lantern in &lanterns {
+                let distance = transform.translation.distance(*lantern);
+                assert!(
+                    distance >= physical_radius - EPSILON,
+                    "moth at {} is {distance} from the lantern at {lantern}",
+                    transform.translation
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn moth_speed_never_exceeds_limit() {
+    run(|world| {
+        let moth_speed = world.resource::<MothConfig>().moth_speed;
+        for velocity in world.query_filtered::<&Velocity, With<Moth>>().iter(world) {
+            assert!(
+                velocity.0.length() <= moth_speed + EPSILON,
+                "moth moving at {} exceeds {moth_speed}",
+                velocity.0.length()
+            );
+        }
+    });
+}
+
+#[test]
+fn lanterns_respect_on_timer_and_cooldown() {
+    let mut switched_at: HashMap<Entity, (bool, f32)> = HashMap::default();
+    let mut cycles = 0;
+
+    run(|world| {
+        let time = world.resource::<Time>();
+        let now = time.elapsed_secs();
+        let step = time.delta_secs();
+
+        for (entity, lantern) in world.query::<(Entity, &Lantern)>().iter(world) {
+            let Some(&(was_on, since)) = switched_at.get(&entity) else {
+                switched_at.insert(entity, (lantern.is_on, now));
+                continue;
+            };
+            if was_on == lantern.is_on {
+                continue;
+            }
+
+            let elapsed = now - since;
+            if was_on {
+                let duration = lantern.on_timer.duration().as_secs_f32();
+                assert!(
+                    elapsed >= duration - EPSILON && elapsed <= duration + step + EPSILON,
+                    "lantern stayed on for {elapsed}s instead of {duration}s"
+                );
+                cycles += 1;
+            } else {
+                let cooldown = lantern.cooldown.duration().as_secs_f32();
+                // Lanterns start with their cooldown already finished, so
+                // only switch-ons that follow a switch-off are checked
+                if since > 0.0 {
+                    assert!(
+                        elapsed >= cooldown - EPSILON,
+                        "lantern came back on after {elapsed}s, cooldown is {cooldown}s"
+                    );
+                }
+            }
+            switched_at.insert(entity, (lantern.is_on, now));
+        }
+    });
+
+    assert!(cycles > 0, "no lantern completed an on/off cycle");
+}
+
+#[test]
+fn spatial_index_tracks_every_lantern() {
+    run(|world| {
+        let lanterns: Vec<(Entity, Vec3)> = world
+            .query_filtered::<(Entity, &Transform), With<Lantern>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, transform.translation))
+            .collect();
+        let index = world.resource::<SpatialIndex>();
+
+        for (entity, position) in &lanterns {
+            // Cells tile the floor plane
+            let tile = (
+                (position.x / CELL_SIZE).floor() as i32,
+                (position.z / CELL_SIZE).floor() as i32,
+            );
+            assert!(
+                index
+                    .cells()
+                    .any(|(cell, entities)| *cell == tile && entities.contains(entity)),
+                "lantern at {position} is missing from cell {tile:?}"
+            );
+        }
+
+        let indexed: usize = index.cells().map(|(_, entities)| entities.len()).sum();
+        assert_eq!(
+            indexed,
+            lanterns.len(),
+            "index holds stale or duplicate entries"
+        );
+    });
+}
+
+#[test]
+fn long_timesteps_advance_in_full() {
+    let timestep = std::time::Duration::from_secs(1);
+    let mut app = headless_app_with_timestep(SEED, timestep);
+    // The first update only starts the clock
+    app.update();
+    app.update();
+    app.update();
+
+    let time = app.world().resource::<Time<Virtual>>();
+    assert_eq!(time.delta(), timestep);
+    assert_eq!(time.elapsed(), timestep * 2);
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy_rand::prelude::{Entropy, WyRand};
+use rand::SeedableRng;
+
+use phototaxis::components::{Lantern, Moth, MothState, Perch, Velocity};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::simulation::headless_app;
+use phototaxis::systems::perch_on;
+
+fn app() -> App {
+    let mut app = headless_app(21);
+    app.insert_resource(MothConfig {
+        moth_count: 1,
+        perch_speed: 0.6,
+        min_perch_secs: 1.0,
+        max_perch_secs: 1.0,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(vec![Vec3::new(0.0, 6.0, 0.0).into()]),
+        on_chance: 0.0,
+        ..default()
+    });
+    app.update();
+    app
+}
+
+fn moth(app: &mut App) -> Entity {
+    let world = app.world_mut();
+    world
+        .query_filtered::<Entity, With<Moth>>()
+        .single(world)
+        .unwrap()
+}
+
+/// Puts the moth just above the floor, flying at `velocity`.
+fn fly_at_floor(app: &mut App, velocity: Vec3) -> Entity {
+    let moth = moth(app);
+    let mut entity = app.world_mut().entity_mut(moth);
+    entity.get_mut::<Transform>().unwrap().translation = Vec3::new(2.0, 0.001, 0.0);
+    entity.get_mut::<Velocity>().unwrap().0 = velocity;
+    moth
+}
+
+#[test]
+fn only_gentle_impacts_land() {
+    let mut rng = Entropy::<WyRand>::seed_from_u64(21);
+    let config = MothConfig::default();
+
+    let perch = perch_on(Vec3::new(0.9, -0.2, 0.0), Vec3::Y, &config, &mut rng).unwrap();
+    assert_eq!(perch.normal, Vec3::Y);
+    assert!(
+        (config.min_perch_secs..=config.max_perch_secs)
+            .contains(&perch.timer.duration().as_secs_f32())
+    );
+    assert!(perch_on(Vec3::new(0.0, -1.0, 0.0), Vec3::Y, &config, &mut rng).is_none());
+    assert!(perch_on(Vec3::new(0.9, 0.2, 0.0), Vec3::Y, &config, &mut rng).is_none());
+}
+
+#[test]
+fn a_backwards_perch_range_rests_for_the_minimum() {
+    let mut rng = Entropy::<WyRand>::seed_from_u64(21);
+    let config = MothConfig {
+        min_perch_secs: 5.0,
+        max_perch_secs: 2.0,
+        ..default()
+    };
+
+    let perch = perch_on(Vec3::new(0.9, -0.2, 0.0), Vec3::Y, &config, &mut rng).unwrap();
+    assert_eq!(perch.timer.duration().as_secs_f32(), 5.0);
+}
+
+#[test]
+fn moths_land_rest_and_take_off() {
+    let mut app = app();
+    let moth = fly_at_floor(&mut app, Vec3::new(0.9, -0.3, 0.0));
+    app.update();
+
+    let perched_at = app.world().get::<Transform>(moth).unwrap().translation;
+    assert_eq!(
+        *app.world().get::<MothState>(moth).unwrap(),
+        MothState::Perched
+    );
+    assert_eq!(app.world().get::<Perch>(moth).unwrap().normal, Vec3::Y);
+    assert_eq!(app.world().get::<Velocity>(moth).unwrap().0, Vec3::ZERO);
+    assert_eq!(perched_at.y, 0.0);
+
+    // Lying flat on the floor
+    let up = app.world().get::<Transform>(moth).unwrap().up();
+    assert!(up.dot(Vec3::Y) > 0.999);
+
+    for _ in 0..30 {
+        app.update();
+    }
+    assert_eq!(
+        app.world().get::<Transform>(moth).unwrap().translation,
+        perched_at
+    );
+
+    for _ in 0..40 {
+        app.update();
+    }
+    assert!(app.world().get::<Perch>(moth).is_none());
+    assert_ne!(
+        *app.world().get::<MothState>(moth).unwrap(),
+        MothState::Perched
+    );
+    assert!(app.world().get::<Transform>(moth).unwrap().translation.y > 0.0);
+}
+
+#[test]
+fn fast_impacts_bounce() {
+    let mut app = app();
+    let moth = fly_at_floor(&mut app, Vec3::new(0.0, -1.0, 0.0));
+    app.update();
+
+    assert!(app.world().get::<Perch>(moth).is_none());
+    assert!(app.world().get::<Velocity>(moth).unwrap().0.y > 0.0);
+}
+
+#[test]
+fn moths_perched_on_a_lantern_move_with_it() {
+    let mut app = app();
+    let moth = moth(&mut app);
+    let world = app.world_mut();
+    let lantern = world
+        .query_filtered::<Entity, With<Lantern>>()
+        .single(world)
+        .unwrap();
+    world.entity_mut(moth).insert((
+        MothState::Perched,
+        Velocity(Vec3::ZERO),
+        Perch {
+            normal: Vec3::NEG_Y,
+            lantern: Some(lantern),
+            offset: Vec3::NEG_Y,
+            timer: Timer::from_seconds(100.0, TimerMode::Once),
+        },
+    ));
+
+    for _ in 0..20 {
+        app.update();
+        let lantern_position = app.world().get::<Transform>(lantern).unwrap().translation;
+        let moth_position = app.world().get::<Transform>(moth).unwrap().translation;
+        assert_eq!(moth_position, lantern_position + Vec3::NEG_Y);
+    }
+
+    // With the lantern gone it has nowhere to sit
+    app.world_mut().despawn(lantern);
+    app.update();
+    assert!(app.world().get::<Perch>(moth).is_none());
+}
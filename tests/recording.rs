@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use phototaxis::components::{Lantern, Moth};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::recording::{LanternEventKind, RecordedEvent, RecordedFrame, Recording};
+use phototaxis::resources::{
+    LanternInteraction, MothSelection, Recorder, ReplayPlayer, ReplayRequests,
+};
+use phototaxis::simulation::headless_app;
+use phototaxis::systems::{replay_close_system, replay_keys_system, replay_load_system};
+
+fn frame(time: f32, x: f32) -> RecordedFrame {
+    RecordedFrame {
+        time,
+        moths: vec![(0, Vec3::new(x, 1.0, 0.0))],
+        lanterns: Vec::new(),
+    }
+}
+
+fn turn_on(time: f32) -> RecordedEvent {
+    RecordedEvent {
+        time,
+        lantern: 0,
+        kind: LanternEventKind::TurnedOn,
+    }
+}
+
+#[test]
+fn recording_a_run_captures_frames_and_switches() {
+    let mut app = headless_app(5);
+    app.insert_resource(MothConfig {
+        moth_count: 20,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Ring {
+            count: 3,
+            radius: 5.0,
+        },
+        on_chance: 1.0,
+        ..default()
+    });
+    // The first update only starts the clock
+    app.update();
+    app.world_mut().resource_mut::<Recorder>().start();
+    for _ in 0..120 {
+        app.update();
+    }
+    let recording = app.world_mut().resource_mut::<Recorder>().stop();
+
+    assert!(recording.frames.len() > 1);
+    assert!(
+        recording
+            .frames
+            .windows(2)
+            .all(|pair| pair[0].time < pair[1].time)
+    );
+    for frame in &recording.frames {
+        assert_eq!(frame.moths.len(), 20);
+        assert_eq!(frame.lanterns.len(), 3);
+    }
+    assert!(
+        recording
+            .events
+            .iter()
+            .any(|event| event.kind == LanternEventKind::TurnedOn)
+    );
+
+    let restored = Recording::from_ron(&recording.to_ron().unwrap()).unwrap();
+    assert_eq!(restored.frames.len(), recording.frames.len());
+    assert_eq!(restored.events.len(), recording.events.len());
+}
+
+#[test]
+fn frames_around_interpolates_between_samples() {
+    let recording = Recording {
+        frames: vec![frame(0.0, 0.0), frame(1.0, 2.0), frame(2.0, 4.0)],
+        events: Vec::new(),
+    };
+
+    let (before, after, t) = recording.frames_around(1.25).unwrap();
+    assert_eq!((before.time, after.time), (1.0, 2.0));
+    assert!((t - 0.25).abs() < 1e-6);
+
+    // Past the end the last frame is held
+    let (before, after, _) = recording.frames_around(5.0).unwrap();
+    assert_eq!((before.time, after.time), (2.0, 2.0));
+
+    assert!(Recording::default().frames_around(0.0).is_none());
+}
+
+#[test]
+fn turn_ons_are_found_either_side_of_a_time() {
+    let recording = Recording {
+        frames: Vec::new(),
+        events: vec![turn_on(1.0), turn_on(3.0)],
+    };
+
+    assert_eq!(
+        recording.next_turn_on(1.0).map(|event| event.time),
+        Some(3.0)
+    );
+    assert_eq!(
+        recording.previous_turn_on(3.0).map(|event| event.time),
+        Some(1.0)
+    );
+    assert!(recording.next_turn_on(3.0).is_none());
+    assert!(recording.previous_turn_on(1.0).is_none());
+}
+
+/// Moth and lantern positions, sorted since a restored scene's entities get
+/// new ids.
+fn scene(app: &mut App) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    fn positions<T: Component>(world: &mut World) -> Vec<[f32; 3]> {
+        let mut positions: Vec<[f32; 3]> = world
+            .query_filtered::<&Transform, With<T>>()
+            .iter(world)
+            .map(|transform| transform.translation.to_array())
+            .collect();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        positions
+    }
+    let world = app.world_mut();
+    (positions::<Moth>(world), positions::<Lantern>(world))
+}
+
+#[test]
+fn closing_a_replay_puts_the_simulation_back() {
+    let mut app = headless_app(5);
+    app.insert_resource(MothConfig {
+        moth_count: 20,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Ring {
+            count: 3,
+            radius: 5.0,
+        },
+        on_chance: 0.5,
+        ..default()
+    })
+    .init_resource::<ReplayRequests>()
+    .init_resource::<MothSelection>()
+    .init_resource::<LanternInteraction>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .add_systems(
+        Update,
+        (replay_keys_system, replay_close_system, replay_load_system).chain(),
+    );
+    for _ in 0..30 {
+        app.update();
+    }
+    // Paused, so nothing moves between saving the scene and putting it back
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    app.update();
+    let live = scene(&mut app);
+
+    let recording = Recording {
+        frames: vec![frame(0.0, 1.0), frame(1.0, 2.0)],
+        events: Vec::new(),
+    };
+    *app.world()
+        .resource::<ReplayRequests>()
+        .load
+        .lock()
+        .unwrap() = Some(recording.to_ron().unwrap());
+    app.update();
+    assert!(app.world().resource::<ReplayPlayer>().recording.is_some());
+    assert_eq!(scene(&mut app), (vec![[0.0; 3]], Vec::new()));
+
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.press(KeyCode::ShiftLeft);
+    keys.press(KeyCode::KeyR);
+    app.update();
+
+    assert!(app.world().resource::<ReplayPlayer>().recording.is_none());
+    assert_eq!(scene(&mut app), live);
+}
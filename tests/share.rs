@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use phototaxis::components::CameraRig;
+use phototaxis::config::{LanternLayout, MAX_MOTHS};
+use phototaxis::share::ShareSettings;
+
+#[test]
+fn settings_survive_a_round_trip() {
+    let layouts = [
+        LanternLayout::Grid { spacing: 4.5 },
+        LanternLayout::Ring {
+            count: 6,
+            radius: 5.0,
+        },
+        LanternLayout::Line {
+            count: 3,
+            spacing: 2.25,
+        },
+        LanternLayout::Random {
+            count: 8,
+            min_spacing: 1.5,
+        },
+        LanternLayout::Clusters {
+            clusters: 2,
+            per_cluster: 4,
+            spread: 1.0,
+        },
+        LanternLayout::Positions(vec![
+            Vec3::new(1.0, 6.0, -2.5).into(),
+            Vec3::new(-3.1, 7.2, 0.0).into(),
+        ]),
+    ];
+
+    for layout in layouts {
+        let settings = ShareSettings {
+            seed: Some(12345678901234),
+            moth_count: Some(300),
+            attraction_factor: Some(1.25),
+            layout: Some(layout),
+            camera: Some(CameraRig {
+                focus: Vec3::new(0.5, 5.0, -1.0),
+                radius: 18.0,
+                angle: 1.2,
+                pitch: -0.2,
+            }),
+        };
+        assert_eq!(ShareSettings::from_query(&settings.to_query()), settings);
+    }
+}
+
+#[test]
+fn missing_and_malformed_keys_are_left_unset() {
+    let settings =
+        ShareSettings::from_query("?seed=7&moths=lots&layout=ring:6&camera=1,2,3&colour=red");
+    assert_eq!(
+        settings,
+        ShareSettings {
+            seed: Some(7),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn layouts_with_bad_distances_are_ignored() {
+    for layout in [
+        "grid:0",
+        "grid:-2",
+        "grid:NaN",
+        "grid:0.5",
+        "ring:6:0",
+        "line:3:-1",
+        "random:4:-1",
+        "clusters:2:3:0",
+        "positions:1,inf,0",
+        "ring:5000:5",
+        "line:1e30:2",
+        "clusters:100:100:1",
+    ] {
+        let settings = ShareSettings::from_query(&format!("layout={layout}"));
+        assert_eq!(settings.layout, None, "{layout}");
+    }
+    assert_eq!(
+        ShareSettings::from_query("layout=random:4:0").layout,
+        Some(LanternLayout::Random {
+            count: 4,
+            min_spacing: 0.0
+        })
+    );
+}
+
+#[test]
+fn oversized_and_non_finite_values_are_ignored() {
+    for query in [
+        "moths=1000000",
+        "moths=-5",
+        "attraction=NaN",
+        "attraction=inf",
+        "attraction=-inf",
+        "camera=0,5,0,NaN,1.57,0.14",
+        "camera=0,inf,0,18,1.57,0.14",
+        "camera=0,5,0,18,1.57,-inf",
+        "camera=0,5,0,0,1.57,0.14",
+    ] {
+        assert_eq!(
+            ShareSettings::from_query(query),
+            ShareSettings::default(),
+            "{query}"
+        );
+    }
+    assert_eq!(
+        ShareSettings::from_query(&format!("moths={MAX_MOTHS}")).moth_count,
+        Some(MAX_MOTHS)
+    );
+}
+
+#[test]
+fn hash_and_escaped_values_are_read() {
+    let settings = ShareSettings::from_query("#attraction=0.5&layout=grid%3A3");
+    assert_eq!(settings.attraction_factor, Some(0.5));
+    assert_eq!(settings.layout, Some(LanternLayout::Grid { spacing: 3.0 }));
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use phototaxis::components::Moth;
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::simulation::headless_app;
+use phototaxis::snapshot::{restore_snapshot, save_snapshot};
+
+const TOLERANCE: f32 = 1e-3;
+
+fn app() -> App {
+    let mut app = headless_app(3);
+    app.insert_resource(MothConfig {
+        moth_count: 50,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Ring {
+            count: 4,
+            radius: 5.0,
+        },
+        on_chance: 0.05,
+        ..default()
+    });
+    app
+}
+
+fn moth_positions(app: &mut App) -> Vec<Vec3> {
+    let world = app.world_mut();
+    let mut positions: Vec<Vec3> = world
+        .query_filtered::<&Transform, With<Moth>>()
+        .iter(world)
+        .map(|transform| transform.translation)
+        .collect();
+    positions.sort_by(|a, b| a.to_array().partial_cmp(&b.to_array()).unwrap());
+    positions
+}
+
+fn run(app: &mut App, ticks: usize) {
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+#[test]
+fn restoring_a_snapshot_replays_the_same_future() {
+    let mut app = app();
+    run(&mut app, 120);
+    let snapshot = save_snapshot(app.world_mut()).unwrap();
+
+    run(&mut app, 60);
+    let expected = moth_positions(&mut app);
+
+    restore_snapshot(app.world_mut(), &snapshot).unwrap();
+    run(&mut app, 60);
+    let actual = moth_positions(&mut app);
+
+    assert_eq!(expected.len(), actual.len());
+    for (want, got) in expected.iter().zip(&actual) {
+        assert!(
+            (*want - *got).abs().max_element() <= TOLERANCE,
+            "expected {want}, got {got}"
+        );
+    }
+}
+
+#[test]
+fn snapshots_restore_into_a_fresh_app() {
+    let mut source = app();
+    run(&mut source, 30);
+    let snapshot = save_snapshot(source.world_mut()).unwrap();
+    let expected = moth_positions(&mut source);
+
+    let mut target = headless_app(99);
+    target.update();
+    restore_snapshot(target.world_mut(), &snapshot).unwrap();
+
+    assert_eq!(
+        target.world().resource::<MothConfig>().moth_count,
+        source.world().resource::<MothConfig>().moth_count
+    );
+    assert_eq!(moth_positions(&mut target), expected);
+}
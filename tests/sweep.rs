@@ -0,0 +1,57 @@
+use phototaxis::config::LanternLayout;
+use phototaxis::sweep::{Sweep, write_csv};
+
+fn sweep() -> Sweep {
+    Sweep {
+        attraction_factor: vec![0.4, 0.8],
+        view_radius: vec![15.0],
+        on_chance: vec![0.05, 0.2],
+        seeds: vec![1, 2],
+        duration_secs: 2.0,
+        moth_count: 20,
+        layout: LanternLayout::Ring {
+            count: 4,
+            radius: 5.0,
+        },
+    }
+}
+
+#[test]
+fn every_combination_is_run_once() {
+    let runs = sweep().runs();
+    assert_eq!(runs.len(), 8);
+    for (i, a) in runs.iter().enumerate() {
+        assert!(runs[i + 1..].iter().all(|b| a != b));
+    }
+}
+
+#[test]
+fn parallel_runs_match_sequential_ones() {
+    let sweep = sweep();
+    let sequential = sweep.run_all(1, |_| {});
+    let parallel = sweep.run_all(4, |_| {});
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn csv_has_a_row_per_run() {
+    let sweep = Sweep {
+        seeds: vec![1, 2, 3],
+        ..sweep()
+    };
+    let summaries = sweep.run_all(2, |_| {});
+    let mut csv = Vec::new();
+    write_csv(&summaries, &mut csv).unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 1 + 4 * 3);
+    assert!(lines[0].starts_with("seed,"));
+}
+
+#[test]
+fn definitions_fill_in_missing_fields() {
+    let sweep = Sweep::from_ron("(seeds: [1, 2, 3], on_chance: [0.0, 0.5])").unwrap();
+    assert_eq!(sweep.runs().len(), 6);
+    assert_eq!(sweep.moth_count, Sweep::default().moth_count);
+}
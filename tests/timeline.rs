@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use phototaxis::components::{Lantern, LanternId, Moth};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::simulation::headless_app;
+use phototaxis::timeline::{Timeline, TimelineAction, TimelinePlayer, TimelinePlugin};
+
+const TIMELINE: &str = r#"(
+    events: [
+        (at: 1.0, action: SpawnMoths(count: 5, position: (0.0, 3.0, 0.0))),
+        (at: 0.5, action: SetLantern(lantern: 1, on: true)),
+        (at: 1.0, action: ScaleConfig(field: "moth.moth_speed", factor: 2.0)),
+        (at: 1.5, action: SetConfig(field: "lantern.on_chance", value: 0.25)),
+    ],
+)"#;
+
+fn app(timeline: Timeline) -> App {
+    let mut app = headless_app(5);
+    app.add_plugins(TimelinePlugin)
+        .insert_resource(TimelinePlayer::new(timeline))
+        .insert_resource(MothConfig {
+            moth_count: 10,
+            ..default()
+        })
+        .insert_resource(LanternConfig {
+            layout: LanternLayout::Line {
+                count: 3,
+                spacing: 2.0,
+            },
+            on_chance: 0.0,
+            ..default()
+        });
+    app.update();
+    app
+}
+
+fn run_for(app: &mut App, secs: f32) {
+    for _ in 0..(secs * 60.0).round() as usize {
+        app.update();
+    }
+}
+
+fn moth_count(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world.query_filtered::<(), With<Moth>>().iter(world).count()
+}
+
+fn lanterns_on(app: &mut App) -> Vec<bool> {
+    let world = app.world_mut();
+    let mut lanterns: Vec<(LanternId, bool)> = world
+        .query::<(&LanternId, &Lantern)>()
+        .iter(world)
+        .map(|(id, lantern)| (*id, lantern.is_on))
+        .collect();
+    lanterns.sort_unstable_by_key(|(id, _)| id.0);
+    lanterns.into_iter().map(|(_, is_on)| is_on).collect()
+}
+
+#[test]
+fn timelines_parse_from_ron() {
+    let timeline = Timeline::from_ron(TIMELINE).unwrap();
+    assert_eq!(timeline.events.len(), 4);
+    assert_eq!(
+        timeline.events[0].action,
+        TimelineAction::SpawnMoths {
+            count: 5,
+            position: Vec3::new(0.0, 3.0, 0.0),
+        }
+    );
+    assert!(Timeline::from_ron("(events: [(at: 1.0, action: Explode)])").is_err());
+}
+
+#[test]
+fn events_happen_once_their_time_comes() {
+    let mut app = app(Timeline::from_ron(TIMELINE).unwrap());
+    let moth_speed = app.world().resource::<MothConfig>().moth_speed;
+
+    run_for(&mut app, 0.4);
+    assert_eq!(lanterns_on(&mut app), [false, false, false]);
+
+    run_for(&mut app, 0.2);
+    assert_eq!(lanterns_on(&mut app), [false, true, false]);
+    assert_eq!(moth_count(&mut app), 10);
+
+    run_for(&mut app, 0.5);
+    assert_eq!(moth_count(&mut app), 15);
+    assert_eq!(
+        app.world().resource::<MothConfig>().moth_speed,
+        moth_speed * 2.0
+    );
+    assert!(!app.world().resource::<TimelinePlayer>().finished());
+
+    run_for(&mut app, 0.5);
+    assert_eq!(app.world().resource::<LanternConfig>().on_chance, 0.25);
+    assert!(app.world().resource::<TimelinePlayer>().finished());
+
+    // Nothing happens twice
+    run_for(&mut app, 1.0);
+    assert_eq!(moth_count(&mut app), 15);
+}
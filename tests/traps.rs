@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use phototaxis::components::{LightTrap, Moth, Velocity};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig, TrapConfig};
+use phototaxis::simulation::headless_app;
+use phototaxis::snapshot::{restore_snapshot, save_snapshot};
+use phototaxis::systems::catch_curves_csv;
+
+const TRAP_POSITION: Vec3 = Vec3::new(0.0, 6.0, 0.0);
+
+fn app() -> App {
+    let mut app = headless_app(9);
+    app.insert_resource(MothConfig {
+        moth_count: 0,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(Vec::new()),
+        on_chance: 0.0,
+        ..default()
+    })
+    .insert_resource(TrapConfig {
+        layout: LanternLayout::Positions(vec![TRAP_POSITION.into()]),
+        ..default()
+    });
+    app.update();
+    app
+}
+
+fn spawn_moth(app: &mut App, position: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((
+            Moth,
+            Transform::from_translation(position),
+            Velocity::default(),
+        ))
+        .id()
+}
+
+fn trap(app: &mut App) -> LightTrap {
+    let world = app.world_mut();
+    world.query::<&LightTrap>().single(world).unwrap().clone()
+}
+
+#[test]
+fn the_bucket_hangs_below_the_lantern() {
+    let trap = LightTrap {
+        capture_radius: 1.0,
+        capture_depth: 2.0,
+        ..default()
+    };
+    let lantern = Vec3::new(1.0, 5.0, 1.0);
+    assert!(trap.contains(lantern, lantern + Vec3::new(0.5, -1.0, 0.5)));
+    assert!(!trap.contains(lantern, lantern + Vec3::new(0.0, 0.5, 0.0)));
+    assert!(!trap.contains(lantern, lantern + Vec3::new(0.0, -2.5, 0.0)));
+    assert!(!trap.contains(lantern, lantern + Vec3::new(1.0, -1.0, 1.0)));
+}
+
+#[test]
+fn traps_catch_moths_in_their_bucket() {
+    let mut app = app();
+    let caught = spawn_moth(&mut app, TRAP_POSITION - Vec3::new(0.0, 0.75, 0.0));
+    let free = spawn_moth(&mut app, TRAP_POSITION + Vec3::new(5.0, -0.75, 0.0));
+
+    for _ in 0..130 {
+        app.update();
+    }
+
+    assert!(app.world().get_entity(caught).is_err());
+    assert!(app.world().get_entity(free).is_ok());
+    let trap = trap(&mut app);
+    assert_eq!(trap.catches, 1);
+    assert_eq!(trap.catch_curve.len(), 2);
+    assert!(trap.catch_curve.iter().all(|&(_, catches)| catches == 1));
+}
+
+#[test]
+fn catch_curves_export_one_row_per_sample() {
+    let trap = LightTrap {
+        catches: 3,
+        catch_curve: vec![(1.0, 1), (2.0, 3)],
+        ..default()
+    };
+    let csv = catch_curves_csv([(4, Vec3::new(1.0, 2.0, 3.0), &trap)]);
+    assert_eq!(csv, "trap,x,y,z,time,catches\n4,1,2,3,1,1\n4,1,2,3,2,3\n");
+}
+
+#[test]
+fn snapshots_keep_traps_and_their_catches() {
+    let mut original = app();
+    spawn_moth(&mut original, TRAP_POSITION - Vec3::new(0.0, 0.75, 0.0));
+    for _ in 0..70 {
+        original.update();
+    }
+    let saved = trap(&mut original);
+    let snapshot = save_snapshot(original.world_mut()).unwrap();
+
+    let mut restored = app();
+    restore_snapshot(restored.world_mut(), &snapshot).unwrap();
+    assert_eq!(trap(&mut restored), saved);
+}
@@ -0,0 +1,92 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{MeshTag, VertexAttributeValues};
+
+use phototaxis::components::{Moth, MothState, Velocity};
+use phototaxis::config::{LanternConfig, LanternLayout, MothConfig};
+use phototaxis::simulation::{HEADLESS_TIMESTEP, MothUpdate, headless_app};
+use phototaxis::systems::wing_flap_system;
+use phototaxis::wings::{WING_ROOT, moth_mesh};
+
+fn app() -> App {
+    let mut app = headless_app(4);
+    app.insert_resource(MothConfig {
+        moth_count: 0,
+        ..default()
+    })
+    .insert_resource(LanternConfig {
+        layout: LanternLayout::Positions(Vec::new()),
+        ..default()
+    })
+    .add_systems(Update, wing_flap_system.after(MothUpdate));
+    app.update();
+    app
+}
+
+fn spawn_moth(app: &mut App, velocity: Vec3, state: MothState) -> Entity {
+    app.world_mut()
+        .spawn((
+            Moth,
+            Transform::from_xyz(0.0, 5.0, 0.0),
+            Velocity(velocity),
+            state,
+            MeshTag(1.0f32.to_bits()),
+        ))
+        .id()
+}
+
+fn phase(app: &App, moth: Entity) -> f32 {
+    f32::from_bits(app.world().get::<MeshTag>(moth).unwrap().0)
+}
+
+#[test]
+fn wingbeats_follow_speed_and_stop_when_perched() {
+    let mut app = app();
+    let config = MothConfig::default();
+    let fast = spawn_moth(&mut app, Vec3::X * config.moth_speed, MothState::Wandering);
+    let slow = spawn_moth(
+        &mut app,
+        Vec3::X * config.moth_speed / 2.0,
+        MothState::Wandering,
+    );
+    let perched = spawn_moth(&mut app, Vec3::ZERO, MothState::Perched);
+    app.update();
+
+    let beat = TAU * config.wingbeat_hz * HEADLESS_TIMESTEP.as_secs_f32();
+    assert!((phase(&app, fast) - (1.0 + beat)).abs() < 1e-4);
+    assert!((phase(&app, slow) - (1.0 + beat / 2.0)).abs() < 1e-4);
+    assert_eq!(phase(&app, perched), 0.0);
+}
+
+#[test]
+fn wingbeat_phase_wraps() {
+    let mut app = app();
+    let moth = spawn_moth(&mut app, Vec3::X, MothState::Attracted);
+    for _ in 0..100 {
+        app.update();
+        assert!((0.0..TAU).contains(&phase(&app, moth)));
+    }
+}
+
+#[test]
+fn the_moth_mesh_has_a_wing_either_side_of_the_body() {
+    let mesh = moth_mesh();
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        panic!("moth mesh has no positions");
+    };
+    assert_eq!(positions.len() % 3, 0);
+    assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+
+    // Only wing vertices lie beyond the hinge, so that's all the shader moves
+    let right: Vec<_> = positions.iter().filter(|p| p[0] > WING_ROOT).collect();
+    let left: Vec<_> = positions.iter().filter(|p| p[0] < -WING_ROOT).collect();
+    assert!(!right.is_empty());
+    assert_eq!(right.len(), left.len());
+    assert!(right.iter().chain(&left).all(|p| p[1] == 0.0));
+    for p in right {
+        assert!(left.iter().any(|q| q[0] == -p[0] && q[2] == p[2]));
+    }
+}